@@ -0,0 +1,272 @@
+// src/cargo.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! `crate:<name>[@version]`: the source of a Cargo dependency.
+//!
+//! The nearest `Cargo.lock` above CWD says which versions are in use and where
+//! each came from:
+//!
+//! | `source` in Cargo.lock | Unpacked at                                           |
+//! | ---------------------- | ----------------------------------------------------- |
+//! | `registry+...`         | `$CARGO_HOME/registry/src/<index>/<name>-<version>`   |
+//! | `git+<url>#<commit>`   | `$CARGO_HOME/git/checkouts/<repo>-<hash>/<short>/...` |
+//! | (none)                 | a path dependency or workspace member                 |
+//!
+//! A crate the lock file does not list (or no lock file at all) falls back to
+//! every unpacked registry copy. `-` and `_` are interchangeable in names, as
+//! Cargo treats them. `@1.2` matches `1.2` and `1.2.x`, but not `1.20`.
+//!
+//! Both files are read with a few line rules, not a TOML parser: Cargo writes
+//! the lock file in a fixed layout, and manifests only need `[package] name`,
+//! `[workspace] members` and `path = "..."` keys.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use crate::resolve_home;
+use crate::workspace::{expand_member, join_lexical};
+
+/// One `[[package]]` of a lock file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub source: Option<String>,
+}
+
+/// Directories holding the source of crate `name`, as seen from `cwd`, one
+/// per version.
+pub fn crate_dirs(cwd: &Path, name: &str, version: Option<&str>) -> Vec<PathBuf> {
+    let lock = find_up(cwd, "Cargo.lock");
+    let packages: Vec<Package> = lock.as_ref()
+        .and_then(|l| std::fs::read_to_string(l).ok())
+        .map(|text| parse_lock(&text))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| same_name(&p.name, name) && version.is_none_or(|v| version_matches(&p.version, v)))
+        .collect();
+    let home = cargo_home();
+
+    let mut dirs = Vec::new();
+    for p in &packages {
+        let dir = match &p.source {
+            Some(s) if s.starts_with("registry+") || s.starts_with("sparse+") => {
+                home.as_ref().and_then(|h| registry_dir(h, &p.name, &p.version))
+            }
+            Some(s) if s.starts_with("git+") => home.as_ref().and_then(|h| git_dir(h, s, &p.name)),
+            Some(_) => None,
+            None => {
+                let start = lock.as_ref().and_then(|l| l.parent()).unwrap_or(cwd);
+                local_dir(start, cwd, &p.name)
+            }
+        };
+        if let Some(d) = dir.filter(|d| !dirs.contains(d)) { dirs.push(d); }
+    }
+    if packages.is_empty() {
+        dirs.extend(home.iter().flat_map(|h| unpacked(h, name, version)));
+    }
+    dirs
+}
+
+/// `$CARGO_HOME`, else `~/.cargo`.
+pub fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME").map(PathBuf::from).or_else(|| resolve_home().ok().map(|h| h.join(".cargo")))
+}
+
+/// The nearest `file` in `dir` or one of its ancestors.
+pub fn find_up(dir: &Path, file: &str) -> Option<PathBuf> {
+    dir.ancestors().map(|d| d.join(file)).find(|f| f.is_file())
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.replace('-', "_") == b.replace('-', "_")
+}
+
+/// `wanted` is the version itself, or a prefix of it that ends where a part does.
+pub fn version_matches(version: &str, wanted: &str) -> bool {
+    version == wanted || version.strip_prefix(wanted).is_some_and(|rest| rest.starts_with(['.', '-', '+']))
+}
+
+/// The `[[package]]` entries of a Cargo.lock.
+pub fn parse_lock(text: &str) -> Vec<Package> {
+    let mut out = Vec::new();
+    let mut current: Option<Package> = None;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            out.extend(current.take().filter(|p| !p.name.is_empty()));
+            if line == "[[package]]" {
+                current = Some(Package { name: String::new(), version: String::new(), source: None });
+            }
+            continue;
+        }
+        let (Some(p), Some((key, value))) = (current.as_mut(), string_key(line)) else { continue };
+        match key {
+            "name" => p.name = value,
+            "version" => p.version = value,
+            "source" => p.source = Some(value),
+            _ => {}
+        }
+    }
+    out.extend(current.filter(|p| !p.name.is_empty()));
+    out
+}
+
+/// `key = "value"`.
+fn string_key(line: &str) -> Option<(&str, String)> {
+    let (key, value) = line.split_once('=')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((key.trim(), value.to_string()))
+}
+
+/// `registry/src/<index>/<name>-<version>`. Each registry (crates.io over git
+/// and over sparse HTTP count as two) has its own index directory.
+fn registry_dir(home: &Path, name: &str, version: &str) -> Option<PathBuf> {
+    let indexes = std::fs::read_dir(home.join("registry/src")).ok()?;
+    indexes.flatten().map(|i| i.path().join(format!("{}-{}", name, version))).find(|d| d.is_dir())
+}
+
+/// Every unpacked registry copy of `name`, one per version.
+fn unpacked(home: &Path, name: &str, version: Option<&str>) -> Vec<PathBuf> {
+    let mut found: Vec<(String, PathBuf)> = Vec::new();
+    let Ok(indexes) = std::fs::read_dir(home.join("registry/src")) else { return Vec::new() };
+    for index in indexes.flatten() {
+        let Ok(entries) = std::fs::read_dir(index.path()) else { continue };
+        for e in entries.flatten() {
+            let file_name = e.file_name();
+            let Some(dir) = file_name.to_str() else { continue };
+            // `serde-1.0.1`, but not `serde-json-1.0.1` or `serde_json-1.0.1`.
+            let Some((n, v)) = split_version(dir) else { continue };
+            if !same_name(n, name) || version.is_some_and(|w| !version_matches(v, w)) { continue; }
+            if found.iter().all(|(seen, _)| seen != v) && e.path().is_dir() { found.push((v.to_string(), e.path())); }
+        }
+    }
+    // Numerically, so 1.10 comes after 1.9.
+    found.sort_by_key(|(v, _)| v.split(['.', '-', '+']).map(|part| part.parse::<u64>().unwrap_or(0)).collect::<Vec<_>>());
+    found.into_iter().map(|(_, p)| p).collect()
+}
+
+/// `name-1.0.0-beta.1` splits at the `-` that starts the version.
+fn split_version(dir: &str) -> Option<(&str, &str)> {
+    let at = dir.match_indices('-').map(|(i, _)| i)
+        .find(|&i| dir[i + 1..].starts_with(|c: char| c.is_ascii_digit()))?;
+    Some((&dir[..at], &dir[at + 1..]))
+}
+
+/// `git/checkouts/<repo>-<hash>/<commit[..7]>`, then the crate inside the
+/// checkout, which may be one member of a workspace.
+fn git_dir(home: &Path, source: &str, name: &str) -> Option<PathBuf> {
+    let (url, commit) = source.trim_start_matches("git+").rsplit_once('#')?;
+    let url = url.split('?').next().unwrap_or(url);
+    let repo = url.trim_end_matches('/').rsplit('/').next()?.trim_end_matches(".git");
+    let short = commit.get(..7).unwrap_or(commit);
+    let checkouts = std::fs::read_dir(home.join("git/checkouts")).ok()?;
+    checkouts.flatten()
+        .filter(|c| c.file_name().to_str().and_then(|n| n.rsplit_once('-')).is_some_and(|(r, _)| r == repo))
+        .map(|c| c.path().join(short))
+        .filter(|d| d.is_dir())
+        .find_map(|d| find_package(&d, name, 2))
+}
+
+/// The directory below `dir` (itself included, `depth` levels deep) whose
+/// manifest declares package `name`.
+fn find_package(dir: &Path, name: &str, depth: usize) -> Option<PathBuf> {
+    if package_name(&dir.join("Cargo.toml")).is_some_and(|n| same_name(&n, name)) { return Some(dir.to_path_buf()); }
+    if depth == 0 { return None; }
+    let mut subdirs: Vec<PathBuf> = std::fs::read_dir(dir).ok()?.flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir() && !p.file_name().is_some_and(|n| n.as_encoded_bytes().starts_with(b".") || n == "target"))
+        .collect();
+    subdirs.sort();
+    subdirs.iter().find_map(|d| find_package(d, name, depth - 1))
+}
+
+/// A path dependency or workspace member: named by a `path = "..."` key or a
+/// `members` entry in the manifests at `root` and `cwd`, or either directory itself.
+fn local_dir(root: &Path, cwd: &Path, name: &str) -> Option<PathBuf> {
+    let mut candidates = vec![root.to_path_buf()];
+    for dir in cwd.ancestors().take_while(|d| d.starts_with(root)) {
+        let Ok(text) = std::fs::read_to_string(dir.join("Cargo.toml")) else { continue };
+        let manifest = Manifest::parse(&text);
+        candidates.push(dir.to_path_buf());
+        candidates.extend(manifest.paths.iter().map(|p| join_lexical(dir, p)));
+        candidates.extend(manifest.members.iter().flat_map(|m| expand_member(dir, m)));
+    }
+    candidates.into_iter()
+        .find(|d| package_name(&d.join("Cargo.toml")).is_some_and(|n| same_name(&n, name)))
+}
+
+/// `[package] name` of a manifest.
+pub fn package_name(manifest: &Path) -> Option<String> {
+    Manifest::parse(&std::fs::read_to_string(manifest).ok()?).name
+}
+
+/// The parts of a Cargo.toml that lead to other crates.
+#[derive(Debug, Default, PartialEq)]
+pub struct Manifest {
+    /// `[package] name`.
+    pub name: Option<String>,
+    /// `[workspace] members`, as written.
+    pub members: Vec<String>,
+    /// Every `path = "..."` in a dependency table.
+    pub paths: Vec<String>,
+}
+
+impl Manifest {
+    pub fn parse(text: &str) -> Self {
+        let mut m = Self::default();
+        let mut table = String::new();
+        let mut in_members = false;
+        let path_key = regex::Regex::new(r#"\bpath\s*=\s*"([^"]*)""#).ok();
+        for line in text.lines() {
+            let line = strip_comment(line).trim();
+            if in_members {
+                m.members.extend(quoted(line));
+                in_members = !line.contains(']');
+                continue;
+            }
+            if line.starts_with('[') {
+                table = line.trim_matches(['[', ']']).trim().to_string();
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else { continue };
+            let (key, value) = (key.trim(), value.trim());
+            match table.as_str() {
+                "package" if key == "name" => m.name = Some(value.trim_matches('"').to_string()),
+                "workspace" if key == "members" => {
+                    let list = value.split(']').next().unwrap_or("");
+                    m.members.extend(quoted(list));
+                    in_members = !value.contains(']');
+                }
+                t if t.ends_with("dependencies") || t.contains("dependencies.") => {
+                    if let Some(c) = path_key.as_ref().and_then(|re| re.captures(line)) {
+                        m.paths.push(c[1].to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        m
+    }
+}
+
+/// `line` up to a `#` that starts a comment: at the start of the line or
+/// after whitespace, and outside a quoted string (`path = "../C#lib"`).
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev = None;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && prev.is_none_or(char::is_whitespace) => return &line[..i],
+            None => {}
+        }
+        prev = Some(c);
+    }
+    line
+}
+
+/// The `"..."` strings in `text`.
+pub fn quoted(text: &str) -> Vec<String> {
+    text.split('"').skip(1).step_by(2).map(String::from).collect()
+}
@@ -0,0 +1,120 @@
+// src/clones.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! `git:<org>/<repo>`: a local clone, found by its remote rather than its
+//! folder name.
+//!
+//! Every search root (CWD, CDPATH, config roots) is walked `CLONE_DEPTH`
+//! levels deep, through the daemon's index when one is running. A directory
+//! with a `.git/config` is a clone and is not descended into. Its `origin`
+//! remote (else its first remote) names it:
+//!
+//! ```text
+//! https://github.com/acme/api.git   ->  github.com/acme/api
+//! git@github.com:acme/api.git       ->  github.com/acme/api
+//! ssh://git@host:2222/acme/api      ->  host/acme/api
+//! ```
+//!
+//! A query names the last parts of that (`api`, `acme/api`, or with the host),
+//! case-insensitively. When nothing matches exactly, a last part that only
+//! contains the query's (`git:acme/ap`) counts. Nothing is fetched.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use crate::identity::FileId;
+use crate::{daemon, search_roots, SearchOptions};
+
+/// How many levels below each root are searched for clones.
+const CLONE_DEPTH: usize = 3;
+
+/// A clone and the parts of its remote URL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repo {
+    pub dir: PathBuf,
+    /// `["github.com", "acme", "api"]`.
+    pub remote: Vec<String>,
+}
+
+/// Every clone below the search roots, in root order, each once.
+pub fn scan(opts: &SearchOptions) -> Vec<Repo> {
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for root in search_roots(opts) {
+        walk(&root.path, CLONE_DEPTH, &mut seen, &mut out);
+    }
+    out
+}
+
+fn walk(dir: &Path, depth: usize, seen: &mut HashSet<FileId>, out: &mut Vec<Repo>) {
+    if !seen.insert(FileId::of(dir)) { return; }
+    if let Ok(config) = std::fs::read_to_string(dir.join(".git").join("config")) {
+        if let Some(remote) = origin_url(&config) {
+            out.push(Repo { dir: dir.to_path_buf(), remote: remote_parts(&remote) });
+        }
+        return;
+    }
+    if depth == 0 { return; }
+    let children = daemon::children(dir).unwrap_or_else(|| daemon::read_children(dir));
+    for child in children {
+        if child.name.as_encoded_bytes().starts_with(b".") || child.name == "node_modules" { continue; }
+        walk(&dir.join(&child.name), depth - 1, seen, out);
+    }
+}
+
+/// The clones whose remote ends in `spec` (`repo`, `org/repo`, ...). Exact
+/// matches win; otherwise the last part may contain the query's last part.
+pub fn matching(clones: &[Repo], spec: &str) -> Vec<PathBuf> {
+    let want: Vec<String> = spec.split('/').filter(|p| !p.is_empty()).map(str::to_lowercase).collect();
+    let Some((last, lead)) = want.split_last() else { return Vec::new() };
+    let tail = |c: &Repo| -> Option<Vec<String>> {
+        let n = c.remote.len().checked_sub(want.len())?;
+        Some(c.remote[n..].iter().map(|p| p.to_lowercase()).collect())
+    };
+    let exact: Vec<PathBuf> = clones.iter().filter(|c| tail(c).is_some_and(|t| t == want)).map(|c| c.dir.clone()).collect();
+    if !exact.is_empty() { return exact; }
+    clones.iter()
+        .filter(|c| tail(c).is_some_and(|t| t[..lead.len()] == *lead && t[lead.len()].contains(last.as_str())))
+        .map(|c| c.dir.clone())
+        .collect()
+}
+
+/// The URL of `[remote "origin"]`, else of the first remote, in a git config.
+pub fn origin_url(config: &str) -> Option<String> {
+    let mut remote: Option<&str> = None;
+    let mut urls: Vec<(String, String)> = Vec::new();
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            remote = line.strip_prefix("[remote \"").and_then(|r| r.strip_suffix("\"]"));
+            continue;
+        }
+        let (Some(name), Some((key, value))) = (remote, line.split_once('=')) else { continue };
+        if key.trim() == "url" { urls.push((name.to_string(), value.trim().to_string())); }
+    }
+    let origin = urls.iter().position(|(name, _)| name == "origin").unwrap_or(0);
+    urls.into_iter().nth(origin).map(|(_, url)| url)
+}
+
+/// `host/org/repo` parts of a remote URL, scp-like (`git@host:org/repo`) or
+/// not, without `.git`. Local paths keep their own components.
+pub fn remote_parts(url: &str) -> Vec<String> {
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.to_string(),
+        // `host:path`, but not `C:\path`.
+        None => match url.split_once(':') {
+            Some((host, path)) if host.len() > 1 && !host.contains('/') => format!("{}/{}", host, path),
+            _ => url.to_string(),
+        },
+    };
+    // Credentials and ports are not part of the name.
+    let rest = rest.rsplit_once('@').filter(|(user, _)| !user.contains('/')).map_or(rest.as_str(), |(_, r)| r);
+    let mut parts: Vec<String> = rest.split(['/', '\\']).filter(|p| !p.is_empty()).map(String::from).collect();
+    if let Some(host) = parts.first_mut() {
+        if let Some((h, port)) = host.split_once(':') {
+            if port.chars().all(|c| c.is_ascii_digit()) { *host = h.to_string(); }
+        }
+    }
+    if let Some(last) = parts.last_mut() {
+        if let Some(repo) = last.strip_suffix(".git") { *last = repo.to_string(); }
+    }
+    parts
+}
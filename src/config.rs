@@ -0,0 +1,165 @@
+// src/config.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Optional settings file.
+//!
+//! Looked up at `$NCD_CONFIG`, else `%APPDATA%\ncd\ncd.conf` on Windows and
+//! `$XDG_CONFIG_HOME/ncd/ncd.conf` (or `~/.config/ncd/ncd.conf`) elsewhere.
+//! A missing file is not an error. The format is one `key = value` per line;
+//! `#` starts a comment at the start of a line or after whitespace, so paths
+//! like `~/src/C#` are read whole. Precedence is CLI flag, then environment, then file.
+//!
+//! ```text
+//! # ~/.config/ncd/ncd.conf
+//! smart-case = true
+//! sources = recent, history
+//! bookmark.work = ~/src/work     # `ncd :work`
+//! tmp = ~/scratch                # base for `ncd --tmp`
+//!
+//! [root ~/bookmarks]      # settings for one CDPATH entry
+//! mode = target
+//! priority = 10
+//!
+//! [root ~/src]
+//! depth = 2
+//! ignore = node_modules, target, .*
+//! ```
+//!
+//! A `[root]` section annotates the CDPATH entry with the same path. Roots
+//! that are not in CDPATH are searched after it.
+
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use crate::{CdMode, NcdError};
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+    pub roots: Vec<RootOptions>,
+}
+
+/// Per-root search settings. Unset fields fall back to the global options.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RootOptions {
+    pub path: PathBuf,
+    /// Overrides `--cd` for this root (e.g. `target` for a bookmark folder).
+    pub mode: Option<CdMode>,
+    /// How many levels below the root are searched (default 1, 0 = none).
+    pub depth: Option<usize>,
+    /// Globs for directory names that are never matched or descended into.
+    pub ignore: Vec<String>,
+    /// Higher priorities are searched, and ranked, first. CWD always leads.
+    pub priority: i64,
+}
+
+impl RootOptions {
+    /// A root with no settings of its own.
+    pub fn plain(path: PathBuf) -> Self {
+        Self { path, ..Default::default() }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "mode" => self.mode = Some(CdMode::parse(value).ok_or_else(|| format!("invalid mode: {}", value))?),
+            "depth" => self.depth = Some(value.parse().map_err(|_| format!("invalid depth: {}", value))?),
+            "priority" => self.priority = value.parse().map_err(|_| format!("invalid priority: {}", value))?,
+            "ignore" => self.ignore = value.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect(),
+            _ => return Err(format!("unknown root setting: {}", key)),
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Reads the config file, if there is one.
+    pub fn load() -> Result<Self, NcdError> {
+        let Some(path) = path() else { return Ok(Self::default()) };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text)
+                .map_err(|e| NcdError::Config(format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(NcdError::Io(e)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        let mut roots: Vec<RootOptions> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() { continue; }
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let path = header.trim().strip_prefix("root ")
+                    .ok_or_else(|| format!("line {}: unknown section [{}]", n + 1, header))?;
+                roots.push(RootOptions::plain(expand_home(path.trim())));
+                continue;
+            }
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", n + 1))?;
+            let (key, value) = (key.trim(), value.trim());
+            match roots.last_mut() {
+                Some(root) => root.set(key, value).map_err(|e| format!("line {}: {}", n + 1, e))?,
+                None => { values.insert(key.to_string(), value.to_string()); }
+            }
+        }
+        Ok(Self { values, roots })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// `bookmark.<name> = <path>` entries, for `:name` queries.
+    pub fn bookmarks(&self) -> HashMap<String, PathBuf> {
+        self.values.iter()
+            .filter_map(|(k, v)| Some((k.strip_prefix("bookmark.")?.to_string(), expand_home(v))))
+            .collect()
+    }
+
+    /// A boolean setting: environment variable `var` first, then `key` in the file.
+    pub fn flag(&self, var: &str, key: &str) -> Result<Option<bool>, NcdError> {
+        if let Ok(v) = env::var(var) {
+            return parse_bool(&v).map(Some).ok_or_else(|| NcdError::Config(format!("{}: not a boolean: {}", var, v)));
+        }
+        match self.get(key) {
+            Some(v) => parse_bool(v).map(Some).ok_or_else(|| NcdError::Config(format!("{}: not a boolean: {}", key, v))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// `line` up to its comment: a `#` at the start or after whitespace.
+fn strip_comment(line: &str) -> &str {
+    let at = line.char_indices()
+        .find(|&(i, c)| c == '#' && line[..i].chars().next_back().is_none_or(char::is_whitespace));
+    at.map_or(line, |(i, _)| &line[..i])
+}
+
+pub fn parse_bool(s: &str) -> Option<bool> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// `~/x` becomes `$HOME/x`; anything else is taken literally.
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
+        Some(rest) => crate::resolve_home().map(|h| h.join(rest)).unwrap_or_else(|_| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Location of the config file (it may not exist).
+pub fn path() -> Option<PathBuf> {
+    if let Some(p) = env::var_os("NCD_CONFIG") { return Some(PathBuf::from(p)); }
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+    };
+    base.map(|b| b.join("ncd").join("ncd.conf"))
+}
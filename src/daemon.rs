@@ -0,0 +1,600 @@
+// src/daemon.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! `ncd daemon`: a directory index that stays fresh.
+//!
+//! The daemon lists every directory below the CDPATH and config roots (to
+//! each root's `depth`) and keeps the listing current through `watch`. The
+//! CLI asks it for a directory's children instead of calling `read_dir`, one
+//! directory at a time, so matching and result order stay in the CLI. Any
+//! directory the daemon does not know, and any error talking to it, falls
+//! back to reading the disk; without a daemon nothing changes.
+//!
+//! Transport is a Unix socket (`$XDG_RUNTIME_DIR/ncd.sock`, else in a 0700
+//! `ncd-<uid>` directory of the temp dir) or a named pipe
+//! (`\\.\pipe\ncd-<user>`, closed to other users and to remote clients);
+//! `NCD_SOCKET` overrides it. The CLI only talks to a socket or pipe owned by
+//! its own user.
+//! The protocol is line based:
+//!
+//! ```text
+//! L <dir>   ->  one `0name` / `1name` line per child (1 = link), then an empty line
+//!           ->  `-` when <dir> is not indexed
+//! S         ->  number of directories indexed
+//! Q         ->  `ok`, then the daemon exits
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use lexopt::{Arg, Parser};
+use crate::identity::FileId;
+use crate::watch::{Change, Watcher};
+use crate::{config, osutil, search_roots, NcdError, SearchOptions};
+
+/// A subdirectory, as the scanner needs it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Child {
+    pub name: OsString,
+    /// The entry itself is a symlink or junction.
+    pub link: bool,
+}
+
+/// Subdirectories of `dir` in name order. Unreadable directories have none.
+pub fn read_children(dir: &Path) -> Vec<Child> {
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    // Ignore files; NCD is strictly for directory navigation.
+    // `is_dir` follows links, so junctions are kept.
+    let mut out: Vec<Child> = entries.flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| Child { link: e.file_type().is_ok_and(|t| t.is_symlink()), name: e.file_name() })
+        .collect();
+    // read_dir order is filesystem dependent; name order keeps results reproducible.
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+/// Children of `dir` from a running daemon, or `None` to read the disk.
+pub fn children(dir: &Path) -> Option<Vec<Child>> {
+    static CONN: OnceLock<Mutex<Option<BufReader<transport::Conn>>>> = OnceLock::new();
+    let conn = CONN.get_or_init(|| Mutex::new(transport::connect(&address()).ok().map(BufReader::new)));
+    let mut guard = conn.lock().ok()?;
+    let reader = guard.as_mut()?;
+    match lookup(reader, dir) {
+        Ok(found) => found,
+        // A daemon that stopped answering is not asked again by this process.
+        Err(_) => { *guard = None; None }
+    }
+}
+
+fn lookup(conn: &mut BufReader<transport::Conn>, dir: &Path) -> io::Result<Option<Vec<Child>>> {
+    let key = dir.as_os_str().as_encoded_bytes();
+    if key.contains(&b'\n') { return Ok(None); }
+    let mut request = b"L ".to_vec();
+    request.extend_from_slice(key);
+    request.push(b'\n');
+    conn.get_mut().write_all(&request)?;
+
+    let mut out = Vec::new();
+    loop {
+        let mut line = Vec::new();
+        if conn.read_until(b'\n', &mut line)? == 0 { return Err(io::ErrorKind::UnexpectedEof.into()); }
+        line.pop();
+        match line.split_first() {
+            None => return Ok(Some(out)),
+            Some((b'-', _)) if out.is_empty() => return Ok(None),
+            Some((flag, name)) => {
+                let Some(name) = osutil::from_vec(name.to_vec()) else { return Ok(None) };
+                out.push(Child { name, link: *flag == b'1' });
+            }
+        }
+    }
+}
+
+/// Where the daemon listens.
+pub fn address() -> OsString {
+    if let Some(a) = std::env::var_os("NCD_SOCKET") { return a; }
+    transport::default_address()
+}
+
+/// Every directory within reach of the roots, with the levels still to descend.
+#[derive(Debug, Default)]
+pub struct Index {
+    dirs: HashMap<PathBuf, Node>,
+    roots: Vec<(PathBuf, usize)>,
+}
+
+#[derive(Debug)]
+struct Node {
+    children: Vec<Child>,
+    /// Levels below this directory that are indexed (at least 1).
+    left: usize,
+}
+
+impl Index {
+    /// Indexes `depth` levels below each root. `watch` is called for every
+    /// directory whose entries are recorded.
+    pub fn build(roots: Vec<(PathBuf, usize)>, watch: &mut dyn FnMut(&Path)) -> Self {
+        let mut index = Self { dirs: HashMap::new(), roots };
+        for (root, depth) in index.roots.clone() {
+            index.add(&root, depth, &mut HashSet::new(), watch);
+        }
+        index
+    }
+
+    fn add(&mut self, dir: &Path, left: usize, visited: &mut HashSet<FileId>, watch: &mut dyn FnMut(&Path)) {
+        if left == 0 || self.dirs.get(dir).is_some_and(|n| n.left >= left) { return; }
+        // Each physical directory is entered once per root, so link cycles end.
+        if !visited.insert(FileId::of(dir)) { return; }
+        let children = read_children(dir);
+        watch(dir);
+        for c in &children { self.add(&dir.join(&c.name), left - 1, visited, watch); }
+        self.dirs.insert(dir.to_path_buf(), Node { children, left });
+    }
+
+    /// Re-reads `dir` after a change: removed subtrees are dropped, new ones indexed.
+    pub fn refresh(&mut self, dir: &Path, watch: &mut dyn FnMut(&Path)) {
+        let Some(node) = self.dirs.get(dir) else { return };
+        let (old, left) = (node.children.clone(), node.left);
+        let new = read_children(dir);
+        for gone in old.iter().filter(|c| !new.contains(c)) {
+            let gone = dir.join(&gone.name);
+            self.dirs.retain(|k, _| !k.starts_with(&gone));
+        }
+        for added in new.iter().filter(|c| !old.contains(c)) {
+            self.add(&dir.join(&added.name), left - 1, &mut HashSet::new(), watch);
+        }
+        if let Some(node) = self.dirs.get_mut(dir) { node.children = new; }
+    }
+
+    /// Starts over, e.g. after the watcher lost events.
+    pub fn rebuild(&mut self, watch: &mut dyn FnMut(&Path)) {
+        *self = Self::build(std::mem::take(&mut self.roots), watch);
+    }
+
+    /// Indexed children of `dir`, or `None` when `dir` is not indexed.
+    pub fn children(&self, dir: &Path) -> Option<&[Child]> {
+        self.dirs.get(dir).map(|n| n.children.as_slice())
+    }
+
+    /// Number of directories known, roots excluded.
+    pub fn count(&self) -> usize {
+        self.dirs.values().map(|n| n.children.len()).sum()
+    }
+}
+
+/// `ncd daemon [--status | --stop]`.
+pub fn main(args: impl IntoIterator<Item = OsString>) -> Result<(), NcdError> {
+    let mut parser = Parser::from_args(args);
+    match parser.next().map_err(|e| NcdError::ArgError(e.to_string()))? {
+        None => serve(),
+        Some(Arg::Long("status")) => {
+            let count = request(b"S\n")?;
+            println!("ncd daemon running at {}: {} directories indexed", address().to_string_lossy(), count);
+            Ok(())
+        }
+        Some(Arg::Long("stop")) => request(b"Q\n").map(|_| ()),
+        Some(arg) => Err(NcdError::ArgError(format!("Unknown daemon option: {}", arg.unexpected()))),
+    }
+}
+
+/// Sends one control command and returns the reply line.
+fn request(command: &[u8]) -> Result<String, NcdError> {
+    let addr = address();
+    let conn = transport::connect(&addr).map_err(|_| {
+        NcdError::Daemon(format!("not running at {}", addr.to_string_lossy()))
+    })?;
+    let mut conn = BufReader::new(conn);
+    let mut line = String::new();
+    conn.get_mut().write_all(command).and_then(|_| conn.read_line(&mut line)).map_err(NcdError::Io)?;
+    Ok(line.trim().to_string())
+}
+
+fn serve() -> Result<(), NcdError> {
+    let addr = address();
+    let listener = transport::bind(&addr)?;
+    let cfg = config::Config::load()?;
+    let opts = SearchOptions { roots: cfg.roots, ..Default::default() };
+    // Root 0 is the daemon's own CWD, which says nothing about the client's.
+    let roots: Vec<(PathBuf, usize)> = search_roots(&opts).into_iter().skip(1)
+        .map(|r| (r.path, r.depth.unwrap_or(1)))
+        .collect();
+
+    let mut watcher = Watcher::new().map_err(NcdError::Io)?;
+    let index = Arc::new(Mutex::new(Index::build(roots, &mut |d| watcher.add(d))));
+    eprintln!("ncd daemon: {} directories indexed, listening at {}",
+        index.lock().map(|i| i.count()).unwrap_or(0), addr.to_string_lossy());
+
+    let shared = Arc::clone(&index);
+    std::thread::spawn(move || {
+        while let Ok(changes) = watcher.next() {
+            let Ok(mut index) = shared.lock() else { return };
+            for change in changes {
+                match change {
+                    Change::Dir(dir) => index.refresh(&dir, &mut |d| watcher.add(d)),
+                    Change::Rescan => index.rebuild(&mut |d| watcher.add(d)),
+                }
+            }
+        }
+    });
+
+    loop {
+        let conn = listener.accept().map_err(NcdError::Io)?;
+        let index = Arc::clone(&index);
+        std::thread::spawn(move || {
+            if let Ok(true) = answer(conn, &index) {
+                transport::unbind(&address());
+                std::process::exit(0);
+            }
+        });
+    }
+}
+
+/// Serves one client until it disconnects. Returns true on a stop request.
+fn answer(conn: transport::Conn, index: &Mutex<Index>) -> io::Result<bool> {
+    let mut conn = BufReader::new(conn);
+    let mut line = Vec::new();
+    while conn.read_until(b'\n', &mut line)? > 0 {
+        line.pop();
+        let reply = match line.as_slice() {
+            b"S" => format!("{}\n", index.lock().map(|i| i.count()).unwrap_or(0)).into_bytes(),
+            b"Q" => { conn.get_mut().write_all(b"ok\n")?; return Ok(true); }
+            [b'L', b' ', dir @ ..] => {
+                let dir = osutil::from_vec(dir.to_vec()).map(PathBuf::from);
+                let index = index.lock().map_err(|e| io::Error::other(e.to_string()))?;
+                listing(dir.as_deref().and_then(|d| index.children(d)))
+            }
+            _ => b"-\n".to_vec(),
+        };
+        conn.get_mut().write_all(&reply)?;
+        line.clear();
+    }
+    Ok(false)
+}
+
+fn listing(children: Option<&[Child]>) -> Vec<u8> {
+    let Some(children) = children else { return b"-\n".to_vec() };
+    // A name the line protocol cannot carry makes the whole directory a miss.
+    if children.iter().any(|c| c.name.as_encoded_bytes().contains(&b'\n')) { return b"-\n".to_vec(); }
+    let mut out = Vec::new();
+    for c in children {
+        out.push(if c.link { b'1' } else { b'0' });
+        out.extend_from_slice(OsStr::as_encoded_bytes(&c.name));
+        out.push(b'\n');
+    }
+    out.push(b'\n');
+    out
+}
+
+#[cfg(unix)]
+mod transport {
+    use std::ffi::{OsStr, OsString};
+    use std::io;
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+    use crate::NcdError;
+
+    pub type Conn = UnixStream;
+
+    extern "C" {
+        fn getuid() -> u32;
+    }
+
+    fn uid() -> u32 {
+        // SAFETY: getuid takes no arguments and cannot fail.
+        unsafe { getuid() }
+    }
+
+    /// Without `XDG_RUNTIME_DIR` the socket lives in `ncd-<uid>`, a 0700
+    /// directory of the temp dir that `bind` creates.
+    fn private_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ncd-{}", uid()))
+    }
+
+    pub fn default_address() -> OsString {
+        match std::env::var_os("XDG_RUNTIME_DIR") {
+            Some(dir) => PathBuf::from(dir).join("ncd.sock").into(),
+            None => private_dir().join("ncd.sock").into(),
+        }
+    }
+
+    pub fn connect(addr: &OsStr) -> io::Result<Conn> {
+        // Another user's socket would feed us their listings.
+        if std::fs::symlink_metadata(addr)?.uid() != uid() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "socket owned by another user"));
+        }
+        let conn = UnixStream::connect(addr)?;
+        // A wedged daemon must not hang the shell; the caller falls back to the disk.
+        conn.set_read_timeout(Some(Duration::from_secs(2)))?;
+        Ok(conn)
+    }
+
+    pub struct Listener(UnixListener);
+
+    pub fn bind(addr: &OsStr) -> Result<Listener, NcdError> {
+        if Path::new(addr).parent() == Some(&private_dir()) { make_private(&private_dir())?; }
+        if UnixStream::connect(addr).is_ok() {
+            return Err(NcdError::Daemon(format!("already running at {}", addr.to_string_lossy())));
+        }
+        // A socket file left by a daemon that did not shut down cleanly.
+        let _ = std::fs::remove_file(addr);
+        UnixListener::bind(addr).map(Listener).map_err(NcdError::Io)
+    }
+
+    pub fn unbind(addr: &OsStr) {
+        let _ = std::fs::remove_file(addr);
+    }
+
+    /// Creates `dir` as 0700, or checks that the one already there is ours
+    /// and closed to others: anyone who can write to it can swap the socket.
+    fn make_private(dir: &Path) -> Result<(), NcdError> {
+        match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+            Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(NcdError::Io(e)),
+            _ => {}
+        }
+        let meta = std::fs::symlink_metadata(dir).map_err(NcdError::Io)?;
+        if !meta.is_dir() || meta.uid() != uid() || meta.mode() & 0o077 != 0 {
+            return Err(NcdError::Daemon(format!("{} is not a private directory of this user", dir.display())));
+        }
+        Ok(())
+    }
+
+    impl Listener {
+        pub fn accept(&self) -> io::Result<Conn> {
+            self.0.accept().map(|(c, _)| c)
+        }
+    }
+}
+
+#[cfg(windows)]
+mod transport {
+    use std::ffi::{c_void, OsStr, OsString};
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Read, Write};
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::{AsRawHandle, FromRawHandle};
+    use std::time::{Duration, Instant};
+    use crate::NcdError;
+
+    /// One end of a pipe. Pipes have no read timeout, so the client polls for
+    /// data itself and gives up after `timeout`, as the Unix socket does.
+    pub struct Conn { pipe: File, timeout: Option<Duration> }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateNamedPipeW(
+            name: *const u16, open_mode: u32, pipe_mode: u32, instances: u32,
+            out_size: u32, in_size: u32, timeout: u32, security: *mut c_void,
+        ) -> *mut c_void;
+        fn ConnectNamedPipe(pipe: *mut c_void, overlapped: *mut c_void) -> i32;
+        fn GetLastError() -> u32;
+        fn GetNamedPipeServerProcessId(pipe: *mut c_void, pid: *mut u32) -> i32;
+        fn OpenProcess(access: u32, inherit: i32, pid: u32) -> *mut c_void;
+        fn GetCurrentProcess() -> *mut c_void;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+        fn PeekNamedPipe(
+            pipe: *mut c_void, buffer: *mut c_void, size: u32,
+            read: *mut u32, available: *mut u32, left: *mut u32,
+        ) -> i32;
+        fn LocalFree(memory: *mut c_void) -> *mut c_void;
+    }
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn OpenProcessToken(process: *mut c_void, access: u32, token: *mut *mut c_void) -> i32;
+        fn GetTokenInformation(token: *mut c_void, class: u32, info: *mut c_void, len: u32, ret_len: *mut u32) -> i32;
+        fn EqualSid(a: *mut c_void, b: *mut c_void) -> i32;
+        fn ConvertSidToStringSidW(sid: *mut c_void, text: *mut *mut u16) -> i32;
+        fn ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            text: *const u16, revision: u32, descriptor: *mut *mut c_void, size: *mut u32,
+        ) -> i32;
+    }
+    #[repr(C)]
+    struct SecurityAttributes { length: u32, descriptor: *mut c_void, inherit: i32 }
+    const PIPE_ACCESS_DUPLEX: u32 = 0x3;
+    const FILE_FLAG_FIRST_PIPE_INSTANCE: u32 = 0x0008_0000;
+    const PIPE_REJECT_REMOTE_CLIENTS: u32 = 0x8;
+    const PIPE_UNLIMITED_INSTANCES: u32 = 255;
+    const SDDL_REVISION_1: u32 = 1;
+    const ERROR_PIPE_CONNECTED: u32 = 535;
+    const INVALID_HANDLE_VALUE: *mut c_void = -1isize as *mut c_void;
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+    const TOKEN_QUERY: u32 = 0x0008;
+    /// `TokenUser` of TOKEN_INFORMATION_CLASS.
+    const TOKEN_USER: u32 = 1;
+
+    pub fn default_address() -> OsString {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        format!(r"\\.\pipe\ncd-{}", user).into()
+    }
+
+    pub fn connect(addr: &OsStr) -> io::Result<Conn> {
+        let pipe = OpenOptions::new().read(true).write(true).open(addr)?;
+        // Any user can create the pipe name first and feed us their listings.
+        if !served_by_us(&pipe)? {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "pipe served by another user"));
+        }
+        // A wedged daemon must not hang the shell; the caller falls back to the disk.
+        Ok(Conn { pipe, timeout: Some(Duration::from_secs(2)) })
+    }
+
+    impl Conn {
+        /// Waits until the pipe has data to read, or is broken, for at most `timeout`.
+        fn wait(&self, timeout: Duration) -> io::Result<()> {
+            let start = Instant::now();
+            loop {
+                let mut available = 0;
+                let null = std::ptr::null_mut();
+                // SAFETY: only the byte count is asked for; the other out-pointers may be null.
+                let ok = unsafe { PeekNamedPipe(self.pipe.as_raw_handle(), null, 0, null.cast(), &mut available, null.cast()) };
+                // A broken pipe is left for `read` to report.
+                if ok == 0 || available > 0 { return Ok(()); }
+                if start.elapsed() >= timeout { return Err(io::ErrorKind::TimedOut.into()); }
+                // Replies usually take microseconds; Sleep's granularity is ~15 ms.
+                if start.elapsed() < Duration::from_millis(5) { std::thread::yield_now(); }
+                else { std::thread::sleep(Duration::from_millis(1)); }
+            }
+        }
+    }
+
+    impl Read for Conn {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if let Some(timeout) = self.timeout { self.wait(timeout)?; }
+            self.pipe.read(buf)
+        }
+    }
+
+    impl Write for Conn {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.pipe.write(buf) }
+        fn flush(&mut self) -> io::Result<()> { self.pipe.flush() }
+    }
+
+    /// Whether the process at the other end of `pipe` runs as this user.
+    fn served_by_us(pipe: &File) -> io::Result<bool> {
+        let mut pid = 0;
+        // SAFETY: `pipe` is the open client end of a named pipe.
+        if unsafe { GetNamedPipeServerProcessId(pipe.as_raw_handle(), &mut pid) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: plain values; a null handle is checked below.
+        let server = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid) };
+        if server.is_null() { return Err(io::Error::last_os_error()); }
+        let theirs = token_user(server);
+        // SAFETY: `server` was opened above and is not used after this.
+        unsafe { CloseHandle(server) };
+        // SAFETY: the pseudo handle of the current process needs no closing.
+        let ours = token_user(unsafe { GetCurrentProcess() })?;
+        let theirs = theirs?;
+        // SAFETY: each buffer starts with a TOKEN_USER whose Sid points into that buffer.
+        Ok(unsafe { EqualSid(theirs[0] as *mut c_void, ours[0] as *mut c_void) } != 0)
+    }
+
+    /// The TOKEN_USER of `process`'s token. Held in u64s so the structure is
+    /// aligned; its first word is the pointer to the user's SID.
+    fn token_user(process: *mut c_void) -> io::Result<Vec<u64>> {
+        let mut token = std::ptr::null_mut();
+        // SAFETY: `process` is a valid process handle; `token` receives a new handle.
+        if unsafe { OpenProcessToken(process, TOKEN_QUERY, &mut token) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut len = 0;
+        // SAFETY: an empty buffer only asks for the size needed.
+        unsafe { GetTokenInformation(token, TOKEN_USER, std::ptr::null_mut(), 0, &mut len) };
+        let mut info = vec![0u64; (len as usize).div_ceil(8)];
+        // SAFETY: `info` holds at least `len` bytes.
+        let ok = unsafe { GetTokenInformation(token, TOKEN_USER, info.as_mut_ptr().cast(), len, &mut len) };
+        let err = io::Error::last_os_error();
+        // SAFETY: `token` was opened above and is not used after this.
+        unsafe { CloseHandle(token) };
+        if ok == 0 || info.is_empty() { return Err(err); }
+        Ok(info)
+    }
+
+    /// A security descriptor whose DACL lets only this user open the pipe;
+    /// the default one lets other local users read the listings.
+    struct OwnerOnly(*mut c_void);
+
+    impl OwnerOnly {
+        fn new() -> io::Result<Self> {
+            // SAFETY: the pseudo handle of the current process needs no closing.
+            let user = token_user(unsafe { GetCurrentProcess() })?;
+            let mut text = std::ptr::null_mut();
+            // SAFETY: `user` starts with a TOKEN_USER whose Sid points into it.
+            if unsafe { ConvertSidToStringSidW(user[0] as *mut c_void, &mut text) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: `text` is a NUL-terminated string that is freed right after.
+            let sid = unsafe {
+                let len = (0..).take_while(|&i| *text.add(i) != 0).count();
+                let sid = String::from_utf16_lossy(std::slice::from_raw_parts(text, len));
+                LocalFree(text.cast());
+                sid
+            };
+            let sddl: Vec<u16> = OsStr::new(&format!("D:P(A;;GA;;;{})", sid)).encode_wide().chain(Some(0)).collect();
+            let mut descriptor = std::ptr::null_mut();
+            // SAFETY: `sddl` is NUL-terminated; the size out-pointer may be null.
+            let ok = unsafe {
+                ConvertStringSecurityDescriptorToSecurityDescriptorW(sddl.as_ptr(), SDDL_REVISION_1, &mut descriptor, std::ptr::null_mut())
+            };
+            if ok == 0 { return Err(io::Error::last_os_error()); }
+            Ok(Self(descriptor))
+        }
+    }
+
+    impl Drop for OwnerOnly {
+        fn drop(&mut self) {
+            // SAFETY: the descriptor was allocated by the conversion above.
+            unsafe { LocalFree(self.0) };
+        }
+    }
+
+    pub struct Listener { name: Vec<u16>, first: std::cell::Cell<bool>, security: OwnerOnly }
+
+    pub fn bind(addr: &OsStr) -> Result<Listener, NcdError> {
+        if connect(addr).is_ok() {
+            return Err(NcdError::Daemon(format!("already running at {}", addr.to_string_lossy())));
+        }
+        let name = addr.encode_wide().chain(Some(0)).collect();
+        let security = OwnerOnly::new().map_err(NcdError::Io)?;
+        Ok(Listener { name, first: std::cell::Cell::new(true), security })
+    }
+
+    /// Pipes vanish with their last handle.
+    pub fn unbind(_addr: &OsStr) {}
+
+    impl Listener {
+        /// Creates a pipe instance and waits for a client on it.
+        pub fn accept(&self) -> io::Result<Conn> {
+            let mode = PIPE_ACCESS_DUPLEX | if self.first.replace(false) { FILE_FLAG_FIRST_PIPE_INSTANCE } else { 0 };
+            let mut security = SecurityAttributes {
+                length: std::mem::size_of::<SecurityAttributes>() as u32,
+                descriptor: self.security.0,
+                inherit: 0,
+            };
+            // SAFETY: `name` is NUL-terminated UTF-16 and `security` outlives the call;
+            // the other arguments are plain values.
+            let pipe = unsafe {
+                CreateNamedPipeW(self.name.as_ptr(), mode, PIPE_REJECT_REMOTE_CLIENTS, PIPE_UNLIMITED_INSTANCES,
+                    4096, 4096, 0, (&mut security as *mut SecurityAttributes).cast())
+            };
+            if pipe == INVALID_HANDLE_VALUE { return Err(io::Error::last_os_error()); }
+            // SAFETY: `pipe` is a valid handle that the File takes ownership of.
+            let file = unsafe { File::from_raw_handle(pipe) };
+            // SAFETY: synchronous wait on the handle owned by `file`.
+            let ok = unsafe { ConnectNamedPipe(pipe, std::ptr::null_mut()) };
+            // A client that connected between the two calls is reported as an error.
+            if ok == 0 && unsafe { GetLastError() } != ERROR_PIPE_CONNECTED {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Conn { pipe: file, timeout: None })
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod transport {
+    use std::ffi::{OsStr, OsString};
+    use std::io;
+    use crate::NcdError;
+
+    pub type Conn = std::fs::File;
+
+    pub fn default_address() -> OsString { OsString::new() }
+
+    pub fn connect(_addr: &OsStr) -> io::Result<Conn> { Err(io::ErrorKind::Unsupported.into()) }
+
+    pub struct Listener;
+
+    pub fn bind(_addr: &OsStr) -> Result<Listener, NcdError> {
+        Err(NcdError::Daemon("not supported on this platform".into()))
+    }
+
+    pub fn unbind(_addr: &OsStr) {}
+
+    impl Listener {
+        pub fn accept(&self) -> io::Result<Conn> { Err(io::ErrorKind::Unsupported.into()) }
+    }
+}
@@ -0,0 +1,48 @@
+// src/fold.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Unicode normalization and case folding for name comparison.
+//!
+//! `to_lowercase()` is not enough to compare directory names:
+//!
+//! * macOS writes names in NFD (`e` + U+0301), everything else in NFC (`é`),
+//!   so the same folder arrives in two byte spellings on a shared drive.
+//! * Full case folding maps `ß` to `ss`, so `STRASSE` finds `straße`.
+//! * Turkish dotted/dotless i (`İ`, `ı`) fold to a plain `i`, since nobody
+//!   types them on purpose in a directory query.
+//!
+//! Every comparison goes through a *key*: NFD, full case fold, optional mark
+//! stripping (`--ignore-accents`), then NFC so one glob `?` spans one letter.
+
+use std::borrow::Cow;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+/// Canonical composition. Names that are already NFC are borrowed.
+pub fn nfc(s: &str) -> Cow<'_, str> {
+    if s.is_ascii() || is_nfc_quick(s.chars()) == IsNormalized::Yes { Cow::Borrowed(s) }
+    else { Cow::Owned(s.nfc().collect()) }
+}
+
+/// The case-insensitive comparison key for `s`.
+pub fn key(s: &str, ignore_accents: bool) -> String {
+    if s.is_ascii() { return s.to_ascii_lowercase(); }
+
+    let folded = caseless::default_case_fold_str(&s.nfd().collect::<String>());
+    let mut out = String::with_capacity(folded.len());
+    let mut prev = '\0';
+    for c in folded.nfd() {
+        // İ folds to i + U+0307; the dot is not part of the letter anyone types.
+        if c == '\u{307}' && prev == 'i' { continue; }
+        if ignore_accents && is_combining_mark(c) { continue; }
+        let c = if c == 'ı' { 'i' } else { c };
+        out.push(c);
+        prev = c;
+    }
+    out.nfc().collect()
+}
+
+/// Case-sensitive equality up to canonical equivalence (NFC == NFD).
+pub fn eq_exact(a: &str, b: &str) -> bool {
+    a == b || nfc(a) == nfc(b)
+}
@@ -0,0 +1,109 @@
+// src/folders.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! `:desktop`, `:downloads` and the other well-known folders.
+//!
+//! | Name        | Unix                                             | Windows                  |
+//! | ----------- | ------------------------------------------------ | ------------------------ |
+//! | `desktop`   | `XDG_DESKTOP_DIR` in user-dirs.dirs, `~/Desktop` | Known Folder `Desktop`   |
+//! | `downloads` | `XDG_DOWNLOAD_DIR`, `~/Downloads`                | Known Folder `Downloads` |
+//! | `documents` | `XDG_DOCUMENTS_DIR`, `~/Documents`               | Known Folder `Documents` |
+//! | `config`    | `$XDG_CONFIG_HOME`, `~/.config`                  | `%APPDATA%`              |
+//! | `cache`     | `$XDG_CACHE_HOME`, `~/.cache`                    | `%LOCALAPPDATA%`         |
+//! | `data`      | `$XDG_DATA_HOME`, `~/.local/share`               | `%LOCALAPPDATA%`         |
+//! | `temp`      | `$TMPDIR`, `/tmp`                                | `%TEMP%`                 |
+//! | `cargo`     | `$CARGO_HOME`, `~/.cargo`                        | the same                 |
+//! | `rustup`    | `$RUSTUP_HOME`, `~/.rustup`                      | the same                 |
+//!
+//! user-dirs.dirs is `$XDG_CONFIG_HOME/user-dirs.dirs`, as `xdg-user-dirs-update`
+//! writes it. Known Folders follow redirection (a Desktop moved to OneDrive).
+//! Bookmarks of the same name come first (see `evaluate`).
+
+use std::env;
+use std::path::PathBuf;
+use crate::{cargo, resolve_home, sources};
+
+/// The well-known folder called `name` (in any case), if it is one.
+pub fn known(name: &str) -> Option<PathBuf> {
+    let home = || resolve_home().ok();
+    match name.to_ascii_lowercase().as_str() {
+        "desktop" => user_dir("DESKTOP", "Desktop"),
+        "downloads" => user_dir("DOWNLOAD", "Downloads"),
+        "documents" => user_dir("DOCUMENTS", "Documents"),
+        "config" if cfg!(windows) => env::var_os("APPDATA").map(PathBuf::from),
+        "config" => env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).or_else(|| home().map(|h| h.join(".config"))),
+        "cache" if cfg!(windows) => env::var_os("LOCALAPPDATA").map(PathBuf::from),
+        "cache" => env::var_os("XDG_CACHE_HOME").map(PathBuf::from).or_else(|| home().map(|h| h.join(".cache"))),
+        "data" => sources::data_dir(),
+        "temp" => Some(env::temp_dir()),
+        "cargo" => cargo::cargo_home(),
+        "rustup" => env::var_os("RUSTUP_HOME").map(PathBuf::from).or_else(|| home().map(|h| h.join(".rustup"))),
+        _ => None,
+    }
+}
+
+/// The Known Folder on Windows; elsewhere `XDG_<xdg>_DIR` from
+/// user-dirs.dirs. Else `~/<fallback>`.
+fn user_dir(xdg: &str, fallback: &str) -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        if let Some(path) = known_folder(fallback) { return Some(path); }
+    }
+    let home = resolve_home().ok()?;
+    let config = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).unwrap_or_else(|| home.join(".config"));
+    let listed = std::fs::read_to_string(config.join("user-dirs.dirs")).ok()
+        .and_then(|text| user_dirs_entry(&text, &format!("XDG_{}_DIR", xdg)));
+    Some(match listed {
+        Some(path) => match path.strip_prefix("$HOME") {
+            Some(rest) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(path),
+        },
+        None => home.join(fallback),
+    })
+}
+
+/// The value of `key="..."` in a user-dirs.dirs file.
+pub fn user_dirs_entry(text: &str, key: &str) -> Option<String> {
+    text.lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .find_map(|l| l.trim().strip_prefix(key)?.trim_start().strip_prefix('='))
+        .map(|v| v.trim().trim_matches('"').to_string())
+}
+
+/// `SHGetKnownFolderPath` for the Desktop, Downloads and Documents folders.
+#[cfg(windows)]
+fn known_folder(name: &str) -> Option<PathBuf> {
+    use std::ffi::{c_void, OsString};
+    use std::os::windows::ffi::OsStringExt;
+
+    #[repr(C)]
+    struct Guid(u32, u16, u16, [u8; 8]);
+    #[link(name = "shell32")]
+    extern "system" {
+        fn SHGetKnownFolderPath(id: *const Guid, flags: u32, token: *mut c_void, path: *mut *mut u16) -> i32;
+    }
+    #[link(name = "ole32")]
+    extern "system" {
+        fn CoTaskMemFree(p: *mut c_void);
+    }
+
+    let id = match name {
+        "Desktop" => Guid(0xB4BFCC3A, 0xDB2C, 0x424C, [0xB0, 0x29, 0x7F, 0xE9, 0x9A, 0x87, 0xC6, 0x41]),
+        "Downloads" => Guid(0x374DE290, 0x123F, 0x4565, [0x91, 0x64, 0x39, 0xC4, 0x92, 0x5E, 0x46, 0x7B]),
+        "Documents" => Guid(0xFDD39AD0, 0x238F, 0x46AF, [0xAD, 0xB4, 0x6C, 0x85, 0x48, 0x03, 0x69, 0xC7]),
+        _ => return None,
+    };
+    let mut raw: *mut u16 = std::ptr::null_mut();
+    // SAFETY: `id` is a valid KNOWNFOLDERID and `raw` a writable pointer; on
+    // success it holds a NUL-terminated string that we free with CoTaskMemFree,
+    // which is also required after a failure.
+    unsafe {
+        let hr = SHGetKnownFolderPath(&id, 0, std::ptr::null_mut(), &mut raw);
+        let path = (hr >= 0 && !raw.is_null()).then(|| {
+            let len = (0..).take_while(|&i| *raw.add(i) != 0).count();
+            PathBuf::from(OsString::from_wide(std::slice::from_raw_parts(raw, len)))
+        });
+        CoTaskMemFree(raw.cast());
+        path
+    }
+}
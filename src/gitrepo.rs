@@ -0,0 +1,121 @@
+// src/gitrepo.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! `wt:<branch-or-name>` and `sub:<name>`: the worktrees and submodules of
+//! the repository around CWD.
+//!
+//! The repository is the nearest directory above CWD with a `.git`. Inside a
+//! linked worktree `.git` is a file (`gitdir: <path>`) that leads back to the
+//! shared git directory, so every worktree sees the same list:
+//!
+//! | Reads                               | Gives                                       |
+//! | ----------------------------------- | ------------------------------------------- |
+//! | `<git dir>/worktrees/<name>/gitdir` | the worktree (the parent of that `.git`)    |
+//! | `<git dir>/worktrees/<name>/HEAD`   | its branch, from `ref: refs/heads/<branch>` |
+//! | `<git dir>/HEAD`                    | the main worktree's branch                  |
+//! | `.gitmodules`                       | `[submodule "<name>"] path = <path>`        |
+//!
+//! A worktree answers to its name or its branch, a submodule to its name, its
+//! path or the path's last part. When the nearest repository has no such
+//! submodule, the repositories above it (a submodule's superproject) are tried.
+
+use std::path::{Path, PathBuf};
+use crate::workspace::join_lexical;
+
+/// A checkout of the repository.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Worktree {
+    pub dir: PathBuf,
+    /// The admin directory name under `.git/worktrees`; none for the main one.
+    pub name: Option<String>,
+    /// `None` on a detached HEAD.
+    pub branch: Option<String>,
+}
+
+/// The git directory of the work tree `dir`: `.git` itself, or where a
+/// `.git` file points.
+fn git_dir(dir: &Path) -> Option<PathBuf> {
+    let dot_git = dir.join(".git");
+    if dot_git.is_dir() { return Some(dot_git); }
+    let text = std::fs::read_to_string(&dot_git).ok()?;
+    let target = text.lines().find_map(|l| l.strip_prefix("gitdir:"))?.trim();
+    Some(join_lexical(dir, target))
+}
+
+/// The directory the worktrees share: a linked worktree's git directory
+/// names it in `commondir`.
+fn common_dir(git_dir: &Path) -> PathBuf {
+    match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(rel) => join_lexical(git_dir, rel.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+/// Every worktree of the repository around `cwd`, the main one first.
+pub fn worktrees(cwd: &Path) -> Vec<Worktree> {
+    let Some(git) = cwd.ancestors().find_map(git_dir) else { return Vec::new() };
+    let common = common_dir(&git);
+    let mut out = Vec::new();
+    // A bare repository has no main worktree.
+    if common.file_name().is_some_and(|n| n == ".git") {
+        if let Some(main) = common.parent() {
+            out.push(Worktree { dir: main.to_path_buf(), name: None, branch: head_branch(&common) });
+        }
+    }
+    let mut admin: Vec<PathBuf> = std::fs::read_dir(common.join("worktrees"))
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    admin.sort();
+    for a in admin {
+        let Ok(gitdir) = std::fs::read_to_string(a.join("gitdir")) else { continue };
+        // Relative with `worktree.useRelativePaths` (git 2.48+): from the admin directory.
+        let gitdir = join_lexical(&a, gitdir.trim());
+        let Some(dir) = gitdir.parent() else { continue };
+        let name = a.file_name().map(|n| n.to_string_lossy().into_owned());
+        out.push(Worktree { dir: dir.to_path_buf(), name, branch: head_branch(&a) });
+    }
+    out
+}
+
+fn head_branch(git_dir: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    head.trim().strip_prefix("ref: refs/heads/").map(String::from)
+}
+
+/// The worktrees called `wanted`, by name or branch.
+pub fn worktree_dirs(cwd: &Path, wanted: &str) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for w in worktrees(cwd) {
+        let hit = w.name.as_deref() == Some(wanted) || w.branch.as_deref() == Some(wanted);
+        if hit && !dirs.contains(&w.dir) { dirs.push(w.dir); }
+    }
+    dirs
+}
+
+/// The submodules called `wanted` in the nearest repository that has one.
+pub fn submodule_dirs(cwd: &Path, wanted: &str) -> Vec<PathBuf> {
+    for dir in cwd.ancestors().filter(|d| d.join(".git").exists()) {
+        let Ok(text) = std::fs::read_to_string(dir.join(".gitmodules")) else { continue };
+        let found: Vec<PathBuf> = submodules(&text).into_iter()
+            .filter(|(name, path)| name == wanted || path == wanted || path.rsplit('/').next() == Some(wanted))
+            .map(|(_, path)| join_lexical(dir, &path))
+            .collect();
+        if !found.is_empty() { return found; }
+    }
+    Vec::new()
+}
+
+/// `(name, path)` of each `[submodule "<name>"]` in a .gitmodules.
+pub fn submodules(text: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut name: Option<String> = None;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            name = line.strip_prefix("[submodule \"").and_then(|r| r.strip_suffix("\"]")).map(String::from);
+            continue;
+        }
+        let (Some(n), Some((key, value))) = (&name, line.split_once('=')) else { continue };
+        if key.trim() == "path" { out.push((n.clone(), value.trim().trim_end_matches('/').to_string())); }
+    }
+    out
+}
@@ -0,0 +1,88 @@
+// src/identity.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Path identity by file ID, used to deduplicate roots and results.
+//!
+//! Two spellings of one directory (`a/../b`, `B` vs `b` on NTFS, a symlink, a
+//! subst drive) share a file ID: `(st_dev, st_ino)` on Unix, `(volume serial,
+//! file index)` on Windows. `canonicalize()` only catches some of these, and
+//! its failures fell back to the raw spelling. When no ID can be read the
+//! identity is the lexically cleaned path, so equal spellings still collapse.
+
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FileId {
+    /// Device (volume) and inode (file index).
+    Id(u64, u64),
+    /// Fallback for paths whose ID cannot be read.
+    Path(PathBuf),
+}
+
+impl FileId {
+    /// Identity of the directory `path` resolves to (links are followed).
+    pub fn of(path: &Path) -> Self {
+        read_id(path).map_or_else(|| Self::Path(lexical(path)), |(dev, ino)| Self::Id(dev, ino))
+    }
+
+    /// Identity of the route itself: equal only for the same spelling.
+    pub fn of_route(path: &Path) -> Self {
+        Self::Path(lexical(path))
+    }
+}
+
+/// Drops `.` components and repeated separators without touching the disk.
+fn lexical(path: &Path) -> PathBuf {
+    path.components().collect()
+}
+
+#[cfg(unix)]
+fn read_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let m = std::fs::metadata(path).ok()?;
+    Some((m.dev(), m.ino()))
+}
+
+#[cfg(windows)]
+fn read_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+
+    // BY_HANDLE_FILE_INFORMATION; `MetadataExt::file_index` is still unstable.
+    #[repr(C)]
+    #[derive(Default)]
+    struct FileInfo {
+        attributes: u32,
+        creation: [u32; 2],
+        access: [u32; 2],
+        write: [u32; 2],
+        volume_serial: u32,
+        size_high: u32,
+        size_low: u32,
+        links: u32,
+        index_high: u32,
+        index_low: u32,
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetFileInformationByHandle(file: *mut std::ffi::c_void, info: *mut FileInfo) -> i32;
+    }
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000; // required to open directories
+
+    let file = std::fs::OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+        .ok()?;
+    let mut info = FileInfo::default();
+    // SAFETY: the handle is open for the duration of the call and `info` is a
+    // correctly laid out, writable BY_HANDLE_FILE_INFORMATION.
+    let ok = unsafe { GetFileInformationByHandle(file.as_raw_handle().cast(), &mut info) };
+    if ok == 0 { return None; }
+    Some((info.volume_serial as u64, (info.index_high as u64) << 32 | info.index_low as u64))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_id(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
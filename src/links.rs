@@ -0,0 +1,66 @@
+// src/links.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Symlink and junction policy (`--links`).
+//!
+//! | Policy   | Matched | Descended | Printed as        | Deduplicated by |
+//! | -------- | ------- | --------- | ----------------- | --------------- |
+//! | follow   | yes     | yes       | path through link | target          |
+//! | logical  | yes     | yes       | path through link | path            |
+//! | physical | yes     | yes       | link target       | target          |
+//! | skip     | no      | no        | -                 | target          |
+//!
+//! `follow` is the historical behaviour. Under `logical`, two links to the same
+//! directory are two results. Directory cycles are never descended twice.
+
+use std::path::{Path, PathBuf};
+use crate::identity::FileId;
+use crate::NcdError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LinkPolicy {
+    #[default]
+    Follow,
+    Skip,
+    Physical,
+    Logical,
+}
+
+impl LinkPolicy {
+    pub fn parse(s: &str) -> Result<Self, NcdError> {
+        match s {
+            "follow" => Ok(Self::Follow),
+            "skip" => Ok(Self::Skip),
+            "physical" => Ok(Self::Physical),
+            "logical" => Ok(Self::Logical),
+            _ => Err(NcdError::ArgError(format!("Invalid link policy: {}", s))),
+        }
+    }
+
+    /// Key for the result `HashSet`: the same directory must only be reported
+    /// once, except under `logical`, where each route to it is its own result.
+    pub fn dedupe_key(self, path: &Path) -> FileId {
+        match self {
+            Self::Logical => FileId::of_route(path),
+            _ => FileId::of(path),
+        }
+    }
+
+    /// The spelling handed back for a match.
+    pub fn present(self, path: PathBuf) -> PathBuf {
+        match self {
+            Self::Physical => path.canonicalize().unwrap_or(path),
+            _ => path,
+        }
+    }
+}
+
+/// True when `path` itself is a symlink or junction (Windows reports both as links).
+pub fn is_link(path: &Path) -> bool {
+    path.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink())
+}
+
+/// The directory a link points at, fully resolved. `None` for plain directories.
+pub fn target(path: &Path) -> Option<PathBuf> {
+    if is_link(path) { path.canonicalize().ok() } else { None }
+}
@@ -0,0 +1,174 @@
+// src/listing.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Sort and filter controls for `--list` output.
+//!
+//! `read_dir` order is filesystem dependent, so `--list` is only reproducible
+//! when a sort key is given. Every key falls back to the full path as a final
+//! tie-breaker, which makes the output identical across runs and machines.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::{links, render_line, NcdError, SearchOptions};
+use crate::pathstyle::PathStyle;
+use crate::rank::rank_candidates;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    /// Directory name, case-insensitive (A..Z).
+    Name,
+    /// Modification time (newest first).
+    Mtime,
+    /// Depth below the search root (shallowest first).
+    Depth,
+    /// Search root order: CWD, then CDPATH (declared order).
+    Root,
+    /// Ranking score (best first), as used by `--first`/`--best`.
+    Score,
+}
+
+impl SortKey {
+    pub fn parse(s: &str) -> Result<Self, NcdError> {
+        match s {
+            "name" => Ok(Self::Name),
+            "mtime" => Ok(Self::Mtime),
+            "depth" => Ok(Self::Depth),
+            "root" => Ok(Self::Root),
+            "score" => Ok(Self::Score),
+            _ => Err(NcdError::ArgError(format!("Invalid sort key: {}", s))),
+        }
+    }
+}
+
+/// Listing controls. Only meaningful together with `--list`.
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    pub sort: Option<SortKey>,
+    pub reverse: bool,
+    /// Keep only directories modified within this window.
+    pub newer_than: Option<Duration>,
+    /// Keep only directories that contain this entry (e.g. `Cargo.toml`).
+    pub contains: Option<std::ffi::OsString>,
+    pub limit: Option<usize>,
+    /// Print the listing as a JSON array (`--json`). Output only, not a filter.
+    pub json: bool,
+}
+
+impl ListOptions {
+    pub fn is_active(&self) -> bool {
+        self.sort.is_some() || self.reverse || self.newer_than.is_some()
+            || self.contains.is_some() || self.limit.is_some()
+    }
+}
+
+/// Filters, sorts and truncates a result set. Idempotent, so it is safe to
+/// apply per root and again on the merged output of a multi-segment query.
+pub fn refine(query: &str, paths: Vec<PathBuf>, opts: &SearchOptions) -> Vec<PathBuf> {
+    let lo = &opts.listing;
+    if !lo.is_active() { return paths; }
+    let now = SystemTime::now();
+
+    let kept: Vec<PathBuf> = paths.into_iter().filter(|p| keep(p, lo, now)).collect();
+
+    // `--reverse` on its own reverses root order, so the result stays a total order.
+    let key = lo.sort.or(if lo.reverse { Some(SortKey::Root) } else { None });
+    let mut out = match key {
+        // No key: keep discovery order.
+        None => kept,
+        Some(key) => {
+            let mut ranked = rank_candidates(query, kept, opts);
+            ranked.sort_by(|a, b| {
+                let ord = match key {
+                    SortKey::Name => name_key(&a.path).cmp(&name_key(&b.path)),
+                    SortKey::Mtime => mtime(&b.path).cmp(&mtime(&a.path)),
+                    SortKey::Depth => a.depth.cmp(&b.depth),
+                    SortKey::Root => a.root.cmp(&b.root),
+                    SortKey::Score => b.score.cmp(&a.score),
+                };
+                let ord = ord.then_with(|| a.path.cmp(&b.path));
+                if lo.reverse { ord.reverse() } else { ord }
+            });
+            ranked.into_iter().map(|r| r.path).collect()
+        }
+    };
+    if let Some(n) = lo.limit { out.truncate(n); }
+    out
+}
+
+/// The per-entry filters (`--contains`, `--newer-than`), which need no other results.
+pub fn keep(p: &Path, lo: &ListOptions, now: SystemTime) -> bool {
+    lo.contains.as_ref().is_none_or(|f| p.join(f).exists())
+        && lo.newer_than.is_none_or(|win| age(p, now).is_some_and(|a| a <= win))
+}
+
+/// Prints one `--list` line. Links are shown as `path -> target`, like `ls -l`.
+pub fn print_entry(path: &Path, style: PathStyle) {
+    let mut line = render_line(path, style);
+    if let Some(target) = links::target(path) {
+        line.extend_from_slice(b" -> ");
+        line.extend(render_line(&target, style));
+    }
+    line.push(b'\n');
+    let _ = std::io::stdout().lock().write_all(&line);
+}
+
+/// Prints the listing as `[{"path": .., "link": .., "target": ..}, ..]`.
+/// Names that are not Unicode are written lossily; JSON strings must be text.
+pub fn print_json(paths: &[PathBuf], style: PathStyle) {
+    let entries: Vec<String> = paths.iter().map(|p| {
+        let text = |p: &Path| json_string(&String::from_utf8_lossy(&render_line(p, style)));
+        let target = links::target(p);
+        format!("{{\"path\":{},\"link\":{},\"target\":{}}}",
+            text(p), target.is_some(), target.as_deref().map_or("null".into(), text))
+    }).collect();
+    println!("[{}]", entries.join(","));
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Parses a relative age such as `90s`, `30m`, `12h`, `7d` or `2w`.
+/// A bare number is taken as days.
+pub fn parse_age(s: &str) -> Result<Duration, NcdError> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let n: u64 = num.parse().map_err(|_| NcdError::ArgError(format!("Invalid age: {}", s)))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "" | "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => return Err(NcdError::ArgError(format!("Invalid age unit: {}", s))),
+    };
+    let total = n.checked_mul(secs).ok_or_else(|| NcdError::ArgError(format!("Age too large: {}", s)))?;
+    Ok(Duration::from_secs(total))
+}
+
+fn name_key(p: &Path) -> String {
+    p.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
+fn mtime(p: &Path) -> Option<SystemTime> {
+    p.metadata().and_then(|m| m.modified()).ok()
+}
+
+fn age(p: &Path, now: SystemTime) -> Option<Duration> {
+    mtime(p).map(|t| now.duration_since(t).unwrap_or_default())
+}
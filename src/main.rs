@@ -14,10 +14,39 @@
 //! 4. **CDPATH Context**: Searching locations defined in the environment.
 
 use std::{env, fmt, process};
-use std::collections::{HashSet};
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use lexopt::{Parser, Arg};
 
+mod cargo;
+mod clones;
+mod config;
+mod daemon;
+mod fold;
+mod folders;
+mod gitrepo;
+mod identity;
+mod links;
+mod listing;
+mod osutil;
+mod pathstyle;
+mod prefix;
+mod query;
+mod rank;
+mod scratch;
+mod sources;
+mod store;
+mod stream;
+mod transfer;
+mod watch;
+mod workspace;
+
+use identity::FileId;
+use pathstyle::PathStyle;
+use query::Segment;
+
 #[cfg(test)]
 mod unit_tests_local;
 #[cfg(test)]
@@ -40,36 +69,90 @@ pub const DRIVE_SEPARATOR: char = char::MAX;
 
 pub const PATH_SEPARATORS: &[char] = &[DOS_SEPARATOR, UNIX_SEPARATOR];
 
+/// How far below each root `--file` looks.
+const FILE_DEPTH: usize = 4;
+
 /// Governs how the engine treats directories found in the `CDPATH`.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum CdMode {
     /// Matches contents *inside* the CDPATH entries (Classic Shell style).
+    #[default]
     Origin,
     /// Matches the CDPATH entry itself (Bookmark style).
     Target,
     /// Matches the entry name first, then its contents (Hybrid style).
     Hybrid
 }
-#[derive(Debug, Clone, Copy, PartialEq)]
+
+impl CdMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "origin" => Some(Self::Origin),
+            "target" => Some(Self::Target),
+            "hybrid" => Some(Self::Hybrid),
+            _ => None,
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum DirMatch {
+    #[default]
     AsIs,
     Fuzzy,
 }
-impl Default for DirMatch {
-    fn default() -> Self {
-        Self::AsIs
-    }
+
+/// What to do when a jump resolves to more than one candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Pick {
+    /// Abort and list the candidates (the "FUBAR" guard).
+    #[default]
+    Strict,
+    /// Take the top ranked candidate, whatever the runner-up scored.
+    First,
+    /// Take the top ranked candidate only if it beats the runner-up by `margin`.
+    Best,
 }
 
 /// Consolidated state to prevent "Parameter Bloat" in the search pipeline.
 /// Using a struct ensures that adding future features (like Frecency)
 /// doesn't require changing every function signature in the project.
+#[derive(Debug, Clone, Default)]
 pub struct SearchOptions {
     pub mode: CdMode,
     pub exact: bool,
     pub list: bool,
     pub dir_match: DirMatch,
+    /// Accent-insensitive matching (`cafe` finds `café`); ignored by `--exact`.
+    pub ignore_accents: bool,
+    /// Segments containing an uppercase letter match case-sensitively (`--smart-case`).
+    pub smart_case: bool,
     pub mock_path: Option<std::ffi::OsString>,
+    pub pick: Pick,
+    /// Minimum score lead the winner needs under `Pick::Best`.
+    pub margin: i64,
+    /// Let recently modified directories win ties (`--recent`).
+    pub rank_mtime: bool,
+    /// Let often and lately visited directories win ties (`--frecent`).
+    pub rank_frecency: bool,
+    /// Sort/filter controls for `--list`; only applied to the final segment.
+    pub listing: listing::ListOptions,
+    /// How symlinks and junctions are matched, printed and deduplicated (`--links`).
+    pub links: links::LinkPolicy,
+    /// Per-root settings from the config file (`[root <path>]` sections).
+    pub roots: Vec<config::RootOptions>,
+    /// History and bookmark sources searched after every root (`--sources`).
+    pub sources: Vec<sources::Source>,
+    /// `--has`: keep only directories holding an entry that matches this glob.
+    pub has: Option<OsString>,
+    /// Scan at least this many levels below every root (`--file` searches deeper).
+    pub min_depth: usize,
+    /// Every search root matching the query is a candidate too, whatever its
+    /// mode (`--file`: the file may sit in CWD itself).
+    pub root_candidates: bool,
+    /// `:name` query anchors (`bookmark.<name>` in the config file).
+    pub bookmarks: HashMap<String, PathBuf>,
+    /// `--mkdir`: create the missing tail of a query that does not resolve.
+    pub mkdir: bool,
 }
 
 fn main() {
@@ -86,18 +169,50 @@ fn main() {
 /// It is specifically designed to handle the "Silent Failure" problem by
 /// ensuring all non-zero exits provide meaningful feedback to the shell.
 fn run() -> Result<(), NcdError> {
-    let mut query: Option<String> = None;
+    // Subcommands; reach a folder with one of these names as `ncd ./daemon`.
+    let mut args = env::args_os().skip(1);
+    match args.next().as_deref().and_then(OsStr::to_str) {
+        Some("daemon") => return daemon::main(args),
+        Some("import") => return transfer::import(args),
+        Some("export") => return transfer::export(args),
+        _ => {}
+    }
+
+    let mut query: Option<OsString> = None;
+    let cfg = config::Config::load()?;
+    let mut style = match env::var("NCD_PATH_STYLE") {
+        Ok(v) => PathStyle::parse(v.trim())?,
+        Err(_) => PathStyle::Native,
+    };
     let mut opts = SearchOptions {
-        mode: match env::var("NCD_MODE").ok().as_deref() {
-            Some("target") => CdMode::Target,
-            Some("hybrid") => CdMode::Hybrid,
-            _ => CdMode::Origin,
-        },
+        mode: env::var("NCD_MODE").ok().and_then(|m| CdMode::parse(&m)).unwrap_or(CdMode::Origin),
         exact: false,
         list: false,
         dir_match: DirMatch::AsIs,
+        ignore_accents: false,
+        smart_case: cfg.flag("NCD_SMART_CASE", "smart-case")?.unwrap_or(false),
         mock_path: None,
+        pick: Pick::Strict,
+        margin: env::var("NCD_MARGIN").ok()
+            .and_then(|m| m.trim().parse().ok())
+            .unwrap_or(rank::DEFAULT_MARGIN),
+        rank_mtime: false,
+        rank_frecency: false,
+        listing: listing::ListOptions::default(),
+        links: links::LinkPolicy::default(),
+        roots: cfg.roots.clone(),
+        sources: match env::var("NCD_SOURCES").ok().or_else(|| cfg.get("sources").map(String::from)) {
+            Some(list) => sources::Source::parse_list(&list)?,
+            None => Vec::new(),
+        },
+        has: None,
+        min_depth: 0,
+        root_candidates: false,
+        bookmarks: cfg.bookmarks(),
+        mkdir: false,
     };
+    let mut file = None;
+    let mut tmp = scratch::TmpOptions::default();
 
     let mut parser = Parser::from_env();
     while let Some(arg) = parser.next().map_err(|e| NcdError::ArgError(e.to_string()))? {
@@ -106,48 +221,104 @@ fn run() -> Result<(), NcdError> {
             Arg::Short('l') | Arg::Long("list") => opts.list = true,
             Arg::Short('e') | Arg::Long("exact") => opts.exact = true,
             Arg::Short('#') | Arg::Long("glob") => opts.dir_match = DirMatch::Fuzzy,
+            Arg::Long("ignore-accents") => opts.ignore_accents = true,
+            Arg::Long("smart-case") => opts.smart_case = true,
+            Arg::Long("no-smart-case") => opts.smart_case = false,
+            Arg::Short('1') | Arg::Long("first") => opts.pick = Pick::First,
+            Arg::Long("best") => opts.pick = Pick::Best,
+            Arg::Long("recent") => opts.rank_mtime = true,
+            Arg::Long("frecent") => opts.rank_frecency = true,
+            Arg::Long("sort") => {
+                opts.listing.sort = Some(listing::SortKey::parse(text_value(&mut parser)?.trim())?);
+            }
+            Arg::Short('r') | Arg::Long("reverse") => opts.listing.reverse = true,
+            Arg::Short('p') | Arg::Long("mkdir") => opts.mkdir = true,
+            Arg::Long("tmp") => tmp.on = true,
+            Arg::Long("clean") => tmp.clean = true,
+            Arg::Long("older-than") => tmp.older_than = Some(listing::parse_age(&text_value(&mut parser)?)?),
+            Arg::Long("newer-than") => {
+                opts.listing.newer_than = Some(listing::parse_age(&text_value(&mut parser)?)?);
+            }
+            Arg::Long("contains") => {
+                // File names are matched losslessly, so keep the raw OS string.
+                opts.listing.contains = Some(parser.value().map_err(|e| NcdError::ArgError(e.to_string()))?);
+            }
+            Arg::Long("limit") => {
+                opts.listing.limit = Some(text_value(&mut parser)?.trim().parse()
+                    .map_err(|_| NcdError::ArgError("Invalid limit.".into()))?);
+            }
+            // Entry names are matched losslessly, like --contains.
+            Arg::Long("has") => opts.has = Some(parser.value().map_err(|e| NcdError::ArgError(e.to_string()))?),
+            Arg::Short('f') | Arg::Long("file") => file = Some(parser.value().map_err(|e| NcdError::ArgError(e.to_string()))?),
+            Arg::Long("sources") => opts.sources = sources::Source::parse_list(&text_value(&mut parser)?)?,
+            Arg::Long("links") => opts.links = links::LinkPolicy::parse(text_value(&mut parser)?.trim())?,
+            Arg::Long("json") => { opts.list = true; opts.listing.json = true; }
+            Arg::Long("path-style") => style = PathStyle::parse(text_value(&mut parser)?.trim())?,
+            Arg::Long("margin") => {
+                opts.margin = text_value(&mut parser)?.trim().parse()
+                    .map_err(|_| NcdError::ArgError("Invalid margin.".into()))?;
+            }
             Arg::Long("cd") => {
-                let val = parser.value().map_err(|e| NcdError::ArgError(e.to_string()))?;
-                opts.mode = match val.to_string_lossy().as_ref() {
-                    "origin" => CdMode::Origin, "target" => CdMode::Target, "hybrid" => CdMode::Hybrid,
-                    _ => return Err(NcdError::ArgError("Invalid cd mode.".into())),
-                };
+                opts.mode = CdMode::parse(&text_value(&mut parser)?)
+                    .ok_or_else(|| NcdError::ArgError("Invalid cd mode.".into()))?;
             }
-            Arg::Value(val) => { query = Some(val.into_string().map_err(NcdError::InvalidUnicode)?); }
+            // Queries stay OS strings: directory names need not be valid Unicode.
+            Arg::Value(val) => { query = Some(val); }
             _ => {}
         }
     }
 
-    // Default to Home (~) if no query is provided.
-    let s = query.unwrap_or_else(|| "~".to_string());
-    let t = s.trim().trim_end_matches(|c| c == DOS_SEPARATOR || c == UNIX_SEPARATOR);
-    let q  = trim_to_elipses(t).to_string();
-
-    match q.as_str() {
-        "" if !s.trim().is_empty() => {
-            println!("{}", DOS_SEPARATOR);
-            return Ok(())
-        }
-        "." => {
-            let p = env::current_dir().map_err(|e| NcdError::ResolutionFailed(e.to_string()))?;
-            println!("{}", p.display()); return Ok(());
-        }
-        ".." => {
-            let p = env::current_dir().map_err(|e| NcdError::ResolutionFailed(e.to_string()))?;
-            println!("{}", p.parent().unwrap_or(&p).display()); return Ok(());
-        }
-        "~" => return resolve_home(),
-        "-" => {
-            let old = env::var_os("OLDPWD").ok_or(NcdError::ResolutionFailed("OLDPWD not set".into()))?;
-            println!("{}", PathBuf::from(old).display()); return Ok(());
-        }
-        _ => {} // Continue to evaluate_jump
+    // --tmp: a new scratch directory instead of a search; the query is its prefix.
+    if tmp.on { return scratch::run(&tmp, query.as_deref(), &cfg, style); }
+    if tmp.clean || tmp.older_than.is_some() {
+        return Err(NcdError::ArgError("--clean and --older-than need --tmp".into()));
+    }
+
+    // --file: any directory within FILE_DEPTH levels that holds the file, the
+    // roots included, or only those matching the query when one is given.
+    // Errors name the file rather than the `*` standing in for a query.
+    let mut label = None;
+    if let Some(name) = file {
+        label = Some(match &query {
+            Some(q) => format!("{} with {}", q.to_string_lossy(), name.to_string_lossy()),
+            None => name.to_string_lossy().into_owned(),
+        });
+        opts.has = Some(name);
+        opts.min_depth = FILE_DEPTH;
+        opts.root_candidates = true;
+        query.get_or_insert_with(|| "*".into());
     }
 
+    // Default to Home (~) if no query is provided.
+    let raw = query.unwrap_or_else(|| "~".into());
+    let q = osutil::trim(&raw);
+    jump(q, &label.unwrap_or_else(|| q.to_string_lossy().into_owned()), &opts, style)
+}
+
+/// Runs the search pipeline for a path query and prints the outcome. `label`
+/// names the query in error messages.
+fn jump(q: &OsStr, label: &str, opts: &SearchOptions, style: PathStyle) -> Result<(), NcdError> {
+    let host = host_query(q, style);
+    let parsed = query::parse(&host).map_err(NcdError::Query)?;
+    let name = q.to_string_lossy();
+    // An unsorted listing of one name is printed as it is found.
+    let lo = &opts.listing;
+    if let Some(plain) = plain_name(&parsed).filter(|_| opts.list && !lo.json && lo.sort.is_none() && !lo.reverse) {
+        return stream_list(plain, label, opts, style);
+    }
     // Execute the Search Pipeline
-    let results = evaluate_jump(&q, &opts);
-    if results.len() > 1 && !opts.list {
-        report_ambiguity(Path::new(&q), results);
+    let mut results = evaluate_located(&host, &parsed, opts)?;
+    if opts.list {
+        // Re-apply on the merged set: multi-segment queries concatenate per-parent listings.
+        results = listing::refine(&name, results, opts);
+    } else if results.len() > 1 {
+        match rank::pick_candidate(&name, results, opts) {
+            Ok(winner) => results = vec![winner],
+            Err(all) => report_ambiguity(Path::new(label), all),
+        }
+    }
+    if results.is_empty() && opts.mkdir && !opts.list {
+        results.extend(create_missing(q, &parsed, opts)?);
     }
 
     // ERROR RESOLUTION & INTEGRATION TEST COMPLIANCE:
@@ -155,354 +326,584 @@ fn run() -> Result<(), NcdError> {
     // before exiting with 1. This prevents the shell wrapper from attempting
     // a null jump and satisfies the integration test predicates.
     if results.is_empty() {
-        eprintln!("NCD Error: Could not resolve \"{}\"", q);
+        eprintln!("NCD Error: Could not resolve \"{}\"", label);
         process::exit(1);
     }
 
     // Output valid paths to stdout for shell capture.
-    for path in results {
-        // UNC paths (\\?\) are stripped to ensure compatibility with standard shell built-ins.
-        println!("{}", path.to_string_lossy().trim_start_matches(r"\\?\"));
+    if opts.listing.json {
+        listing::print_json(&results, style);
+    } else if opts.list {
+        for path in results { listing::print_entry(&path, style); }
+    } else {
+        if let [path] = results.as_slice() {
+            // Best effort: a read-only data directory must not block the jump.
+            if opts.rank_frecency || opts.sources.contains(&sources::Source::Recent) { let _ = store::record(path); }
+        }
+        for path in results { emit(&path, style); }
     }
     Ok(())
 }
 
-// --- CORE NAVIGATION ENGINE ---
+/// `--mkdir`: resolves the longest prefix of `query` that exists, then creates
+/// the rest of it there. Only plain names are created, and only below a
+/// single directory: an ambiguous prefix is reported, not guessed at.
+fn create_missing(q: &OsStr, query: &query::Query, opts: &SearchOptions) -> Result<Option<PathBuf>, NcdError> {
+    let refuse = |s: &Segment| NcdError::ArgError(format!("--mkdir only creates plain names, not \"{}\"", s.spelling().to_string_lossy()));
+    if let Some(s) = query.segments.iter().find(|s| matches!(s, Segment::Glob(_) | Segment::Regex(_))) {
+        return Err(refuse(s));
+    }
+    for k in (0..query.segments.len()).rev() {
+        let prefix = query::Query { anchor: query.anchor.clone(), segments: query.segments[..k].to_vec() };
+        let mut found = evaluate(&prefix, opts)?;
+        if found.len() > 1 { report_ambiguity(Path::new(q), found); }
+        let Some(dir) = found.pop() else { continue };
+        let mut target = dir;
+        for s in &query.segments[k..] {
+            match s {
+                Segment::Literal(name) => target.push(name),
+                Segment::Here => {}
+                _ => return Err(refuse(s)),
+            }
+        }
+        std::fs::create_dir_all(&target).map_err(NcdError::Io)?;
+        return Ok(Some(target));
+    }
+    Ok(None)
+}
+
+/// The name of a query that is a single name or glob searched from CWD: no
+/// anchor (`~` and `-` included), navigation tokens or further segments.
+fn plain_name(query: &query::Query) -> Option<&OsStr> {
+    use query::Anchor;
+    match (&query.anchor, query.segments.as_slice()) {
+        (Anchor::Relative, [Segment::Literal(name) | Segment::Glob(name)]) => Some(name),
+        _ => None,
+    }
+}
 
-/// The central brain of NCD. It deconstructs the user query and routes it
-/// through specialized logic handlers (Ellipsis, Anchors, or CDPATH Search).
-pub fn evaluate_jump(raw_query: &str, opts: &SearchOptions) -> Vec<PathBuf> {
-    let query = raw_query.trim();
-    if query.is_empty() { return vec![]; }
+/// `--list` for a plain name, printed while the search runs. The search stops
+/// as soon as `--limit` lines are out, instead of walking every root first.
+fn stream_list(q: &OsStr, label: &str, opts: &SearchOptions, style: PathStyle) -> Result<(), NcdError> {
     let base = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let opts = SearchOptions { exact: is_case_sensitive(q, opts), smart_case: false, ..opts.clone() };
+    let (events, cancel) = stream::spawn(disk_name(&base, q), &opts);
+    let now = std::time::SystemTime::now();
+    let mut printed = 0;
+    for event in events {
+        if opts.listing.limit.is_some_and(|n| printed >= n) { break; }
+        let stream::Event::Match(c) = event else { continue };
+        if !listing::keep(&c.path, &opts.listing, now) { continue; }
+        listing::print_entry(&c.path, style);
+        printed += 1;
+        // Same rule as search_cdpath: a case-insensitive direct hit is the answer.
+        let direct = c.phase == stream::Phase::Direct && !opts.exact;
+        if direct || opts.listing.limit.is_some_and(|n| printed >= n) { break; }
+    }
+    cancel.cancel();
+
+    if printed == 0 {
+        eprintln!("NCD Error: Could not resolve \"{}\"", label);
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Reads an option value that must be text (modes, keys, numbers).
+fn text_value(parser: &mut Parser) -> Result<String, NcdError> {
+    parser.value().map_err(|e| NcdError::ArgError(e.to_string()))?
+        .into_string().map_err(NcdError::InvalidUnicode)
+}
+
+/// Writes one resolved path to stdout in the caller's path style.
+fn emit(path: &Path, style: PathStyle) {
+    let mut out = std::io::stdout().lock();
+    let _ = out.write_all(&render_line(path, style)).and_then(|_| out.write_all(b"\n"));
+}
+
+/// The bytes `emit` prints for `path`.
+fn render_line(path: &Path, style: PathStyle) -> Vec<u8> {
+    match path.to_str() {
+        // Verbatim prefixes (\\?\) are dropped where a plain spelling exists, so shell
+        // built-ins can use the path; long paths and device paths keep theirs.
+        Some(_) => prefix::normalize_output(&pathstyle::render(path, style)).into_bytes(),
+        // Names that are not Unicode have no translated spelling: hand over the OS bytes.
+        None => path.as_os_str().as_encoded_bytes().to_vec(),
+    }
+}
+
+// --- CORE NAVIGATION ENGINE ---
 
-    let starts_with_sep = query.starts_with(std::path::is_separator);
-    let (bare, is_anchored, components) = get_drive_components(query);
-    if bare {
-        return vec![PathBuf::from(query)];
+/// The central brain of NCD. The query is parsed (see `query`), the anchor
+/// picks where the search starts, and the segments are resolved from there.
+/// Unparseable queries resolve to nothing.
+#[cfg(test)]
+pub fn evaluate_jump(raw_query: impl AsRef<OsStr>, opts: &SearchOptions) -> Vec<PathBuf> {
+    let host = host_query(raw_query.as_ref(), PathStyle::Native);
+    query::parse(&host).ok()
+        .and_then(|q| evaluate_located(&host, &q, opts).ok())
+        .unwrap_or_default()
+}
+
+/// `evaluate`, then, when that finds nothing, the same query without its
+/// `:line[:col]` location. The whole text goes first, so a folder really
+/// named `build:2` is not mistaken for `build` at line 2. `host` is the text
+/// `parsed` came from.
+fn evaluate_located(host: &OsStr, parsed: &query::Query, opts: &SearchOptions) -> Result<Vec<PathBuf>, NcdError> {
+    let whole = evaluate(parsed, opts);
+    if matches!(&whole, Ok(found) if !found.is_empty()) { return whole; }
+    let Some(bare) = query::without_location(host).and_then(|b| query::parse(b).ok()) else { return whole };
+    match evaluate(&bare, opts) {
+        Ok(found) if !found.is_empty() => Ok(found),
+        _ => whole,
     }
+}
 
-    let (head, tails) = split_query(components, starts_with_sep, is_anchored);
+/// Foreign drive spellings (/c/x, /mnt/c/x, /cygdrive/c/x, C:\x) become host paths,
+/// so the parser only ever sees a native anchor. `style` is the --path-style.
+fn host_query(raw: &OsStr, style: PathStyle) -> OsString {
+    match raw.to_str() {
+        Some(text) => pathstyle::to_host(text.trim(), style).into(),
+        None => osutil::trim(raw).to_os_string(),
+    }
+}
 
-    // If not anchored, the 'head' is our first search term
-    let (start_roots, segments) = if is_anchored || starts_with_sep {
-        let sep = PATH_SEPARATORS[0];
-        let root = if starts_with_sep {
+/// Routes a parsed query: the anchor gives the starting directory, and
+/// `resolve_path_segments` walks the segments from it.
+fn evaluate(query: &query::Query, opts: &SearchOptions) -> Result<Vec<PathBuf>, NcdError> {
+    use query::Anchor;
+    let base = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let segments = query.segments.clone();
+    let sep = PATH_SEPARATORS[0];
+    let bare = segments.is_empty();
+    let start = match &query.anchor {
+        // Naked query: Start at CWD, and the whole query parts are segments
+        Anchor::Relative => return Ok(resolve_path_segments(vec![base], segments, opts)),
+        // A lone anchor is already a path.
+        Anchor::Root if bare => return Ok(vec![PathBuf::from(DOS_SEPARATOR.to_string())]),
+        Anchor::Drive(d) | Anchor::Prefix(d) if bare => return Ok(vec![PathBuf::from(d)]),
+        Anchor::Root => {
             let r = get_drive_root(&base).unwrap_or_else(|| PathBuf::from(sep.to_string()));
             PathBuf::from(format!("{}{}", r.display().to_string().trim_end_matches(PATH_SEPARATORS), sep))
-        } else {
-            PathBuf::from(format!("{}{}", head, sep))
-        };
-        (vec![root], tails)
-    } else {
-        // Naked query: Start at CWD, and the whole query parts are segments
-        let mut s = Vec::new();
-        if !head.is_empty() { s.push(head); }
-        s.extend(tails);
-        (vec![base], s)
+        }
+        Anchor::Drive(d) | Anchor::Prefix(d) => {
+            let mut r = d.clone();
+            if !osutil::utf8_lead(d).ends_with(PATH_SEPARATORS) { r.push(sep.to_string()); }
+            PathBuf::from(r)
+        }
+        Anchor::Home => resolve_home()?,
+        Anchor::Previous => {
+            let old = env::var_os("OLDPWD").ok_or(NcdError::ResolutionFailed("OLDPWD not set".into()))?;
+            // OLDPWD may have been set by a different shell layer (e.g. Git Bash's /c/...,
+            // which MSYSTEM gives away).
+            match old.to_str() { Some(o) => PathBuf::from(pathstyle::to_host(o, PathStyle::Native)), None => PathBuf::from(old) }
+        }
+        // Bookmarks, then well-known folders, then workspace members.
+        Anchor::Bookmark(name) => match opts.bookmarks.get(name).cloned().or_else(|| folders::known(name)) {
+            Some(dir) => dir,
+            None => {
+                let dirs = workspace::member_dirs(&base, name);
+                if dirs.is_empty() {
+                    return Err(NcdError::UnknownBookmark(name.clone()));
+                }
+                return Ok(resolve_below(dirs, segments, opts));
+            }
+        },
+        Anchor::Crate { name, version } => return Ok(resolve_below(cargo::crate_dirs(&base, name, version.as_deref()), segments, opts)),
+        Anchor::Member(name) => return Ok(resolve_below(workspace::member_dirs(&base, name), segments, opts)),
+        Anchor::Remote(first) => {
+            let repos = clones::scan(opts);
+            return Ok(resolve_longest(first, segments, opts, |spec| clones::matching(&repos, spec)));
+        }
+        Anchor::Worktree(first) => return Ok(resolve_longest(first, segments, opts, |name| gitrepo::worktree_dirs(&base, name))),
+        Anchor::Submodule(first) => return Ok(resolve_longest(first, segments, opts, |name| gitrepo::submodule_dirs(&base, name))),
     };
+    // PATH-LOCK: below an anchor, CWD and CDPATH play no part.
+    let locked = SearchOptions { mock_path: Some(start.clone().into_os_string()), ..opts.clone() };
+    Ok(resolve_path_segments(vec![start], segments, &locked))
+}
 
-    resolve_path_segments(start_roots, segments, opts)
+/// `resolve_path_segments` from anchors that may name several directories (two
+/// versions of a crate, two members of one name); more than one result is left
+/// to the ambiguity check.
+fn resolve_below(dirs: Vec<PathBuf>, segments: Vec<Segment>, opts: &SearchOptions) -> Vec<PathBuf> {
+    let Some(first) = dirs.first() else { return dirs };
+    let locked = SearchOptions { mock_path: Some(first.clone().into_os_string()), ..opts.clone() };
+    resolve_path_segments(dirs, segments, &locked)
+}
+
+/// For anchors whose names may hold `/` (`git:acme/api`, `wt:feature/x`,
+/// `sub:libs/core`): the longest run of segments that `find` knows wins, and
+/// the rest is walked below it.
+fn resolve_longest(first: &str, segments: Vec<Segment>, opts: &SearchOptions, find: impl Fn(&str) -> Vec<PathBuf>) -> Vec<PathBuf> {
+    for k in (0..=segments.len()).rev() {
+        let name = segments[..k].iter().fold(first.to_string(), |s, p| format!("{}/{}", s, p.spelling().to_string_lossy()));
+        let dirs = find(&name);
+        if !dirs.is_empty() { return resolve_below(dirs, segments[k..].to_vec(), opts); }
+    }
+    Vec::new()
 }
 
-fn resolve_path_segments(matches: Vec<PathBuf>, mut segments: Vec<&str>, opts: &SearchOptions) -> Vec<PathBuf> {
-    segments.retain(|&s| !s.trim().is_empty() && s.trim() != ".");
+/// Walks `segments` down from every directory in `matches`. Text segments
+/// (as tests write them) are read as the parser would.
+fn resolve_path_segments<S: Into<Segment>>(matches: Vec<PathBuf>, segments: Vec<S>, opts: &SearchOptions) -> Vec<PathBuf> {
+    let mut segments: Vec<Segment> = segments.into_iter().map(Into::into).filter(|s| *s != Segment::Here).collect();
     if segments.is_empty() || matches.is_empty() { return matches; }
 
     let segment = segments.remove(0);
-    let mut next_matches = Vec::new();
+    let more = !segments.is_empty();
+    let mut resolved = Vec::new();
+
+    // Options for this segment only; the recursion below starts again from `opts`.
+    // Listing filters (e.g. --contains) describe the final directory, not the route to it.
+    // Smart-case is decided by what the user typed for this segment, before
+    // search_by_fullname swaps in the on-disk spelling.
+    let seg_opts = SearchOptions {
+        listing: if more { Default::default() } else { opts.listing.clone() },
+        has: if more { None } else { opts.has.clone() },
+        exact: is_case_sensitive(&segment.spelling(), opts),
+        smart_case: false,
+        ..opts.clone()
+    };
 
     for path in matches {
-        let nav = trim_to_elipses(segment);
-        if is_ellipsis(&nav) {
-            next_matches.extend(handle_ellipsis(&nav, path));
-        } else if nav == ".." {
-            if let Some(parent) = path.parent() {
-                next_matches.push(parent.to_path_buf());
+        let before = resolved.len();
+        let is_base_cwd = env::current_dir().map(|c| c == path).unwrap_or(false);
+        let groups: Box<dyn Iterator<Item = Vec<PathBuf>>> = match segment {
+            Segment::Up(1) => Box::new(std::iter::once(path.parent().map(Path::to_path_buf).into_iter().collect())),
+            Segment::Up(levels) => Box::new(std::iter::once(handle_ellipsis(levels, path.clone()))),
+            _ => {
+                if is_base_cwd && !more {
+                    // A single name: search_cdpath's rules, ambiguity reporting included.
+                    Box::new(std::iter::once(search_by_fullname(&path, &segment, &seg_opts)))
+                } else if is_base_cwd {
+                    // First segment of a chain: check CDPATH/CWD
+                    Box::new(segment_groups(&path, &segment, &seg_opts))
+                } else {
+                    // Sub-segments: LOCK to the specific folder found
+                    let locked_opts = SearchOptions {
+                        mock_path: Some(path.clone().into_os_string()),
+                        ..seg_opts.clone()
+                    };
+                    Box::new(segment_groups(&path, &segment, &locked_opts))
+                }
             }
-        } else {
-            let is_base_cwd = env::current_dir().map(|c| c == path).unwrap_or(false);
-
-            let found = if is_base_cwd {
-                // First segment: check CDPATH/CWD
-                search_by_fullname(&path, &segment.to_string(), opts)
-            } else {
-                // Sub-segments: LOCK to the specific folder found
-                let locked_opts = SearchOptions {
-                    mode: opts.mode,
-                    exact: opts.exact,
-                    list: opts.list,
-                    dir_match: opts.dir_match,
-                    mock_path: Some(path.clone().into_os_string()),
-                };
-                search_by_fullname(&path, &segment.to_string(), &locked_opts)
+        };
+
+        // BACKTRACKING: candidates that dead-end further down the chain are
+        // dropped, and the next root is only searched when a whole root does.
+        // Ambiguity is left to the caller, which sees the complete chains.
+        for group in groups.filter(|g| !g.is_empty()) {
+            // Anchor the next segment's search at this candidate set.
+            let next_opts = SearchOptions {
+                mock_path: Some(group[0].clone().into_os_string()),
+                ..opts.clone()
             };
+            let found = resolve_path_segments(group, segments.clone(), &next_opts);
+            if !found.is_empty() { resolved.extend(found); break; }
+        }
 
-            next_matches.extend(found);
+        // A file as the last segment (pasted from a compiler error or a stack
+        // trace) lands in its directory, unless a directory matched the name.
+        // A bare file name is looked for in every search root, first root first.
+        if let (Segment::Literal(name), false) = (&segment, more) {
+            if resolved.len() == before {
+                let dirs = if is_base_cwd { search_roots(&seg_opts).into_iter().map(|r| r.path).collect() } else { vec![path] };
+                resolved.extend(dirs.into_iter().find(|d| d.join(name).is_file()));
+            }
         }
     }
-
-    if next_matches.is_empty() { return Vec::new(); }
-
-    // THE RECURSION FIX:
-    // Create the new options for the next level.
-    // We use the first match as the anchor for the next segment's search.
-    let next_opts = SearchOptions {
-        mode: opts.mode,
-        exact: opts.exact,
-        list: opts.list,
-        dir_match: opts.dir_match,
-        mock_path: Some(next_matches[0].clone().into_os_string()),
-    };
-
-    resolve_path_segments(next_matches, segments, &next_opts)
+    resolved
 }
 
-/// The main search loop. It iterates through possible search roots (CWD, CDPATH)
-/// and applies a 3-phase matching strategy to each.
-pub fn search_cdpath(name: &str, opts: &SearchOptions) -> Vec<PathBuf> {
-    let engine = SearchEngine::new(name, opts.exact);
-    let mut all_matches = Vec::new();
-    let mut dirs = HashSet::new();
-    let roots = get_search_roots(&opts.mock_path);
-
-    for (i, root) in roots.into_iter().enumerate() {
-        if !root.is_dir() { continue; }
-        let mut matches = Vec::new();
-        let canon_root = root.canonicalize().unwrap_or_else(|_| root.clone());
-        let is_mock_search = opts.mock_path.is_some();
-
-        // PHASE A: DIRECT CHILD HIT (Absolute/Relative paths)
-        if !engine.is_wildcard && !name.is_empty() {
-            if let Some(path) = engine.check_direct(&root) {
-                let d = path.canonicalize().unwrap_or_else(|_| path.clone());
-                if !opts.exact { return vec![path]; }
-                if dirs.insert(d) { matches.push(path); }
-            }
-        }
+/// One query segment's candidates below `path`, as `root_groups` yields them.
+fn segment_groups(path: &Path, segment: &Segment, opts: &SearchOptions) -> impl Iterator<Item = Vec<PathBuf>> {
+    let segment = disk_segment(path, segment);
+    let label = segment.spelling().to_string_lossy().into_owned();
+    let opts = opts.clone();
+    root_groups(&segment, &opts).1.map(move |(_, group)| {
+        if opts.list { listing::refine(&label, group, &opts) } else { group }
+    })
+}
 
-        // Phase B: for CWD, wildcard matching, any mode.
-        if engine.is_wildcard && i == 0 {
-            for p in engine.scan_dir(&root, opts) {
-                let d = p.canonicalize().unwrap_or_else(|_| p.clone());
-                if dirs.insert(d) { matches.push(p); }
-            }
-        }
+/// Listings, globs and ranked picks weigh every root's candidates together.
+fn collects_all(opts: &SearchOptions, is_wildcard: bool) -> bool {
+    opts.list || opts.pick != Pick::Strict || is_wildcard
+}
 
-        // PHASE C: TARGET (The folder itself is the bookmark)
-        if (i > 0 || is_mock_search) && opts.mode != CdMode::Origin {
-            if engine.matches_path(&root) {
-                if dirs.insert(canon_root.clone()) { matches.push(root.clone()); }
+/// Candidates for `name`, one group per root in root order, searched lazily:
+/// a root is only read when the caller asks for the next group. Under
+/// `collects_all` there is a single group holding every root's matches. A
+/// case-insensitive direct hit comes first, as a group of its own.
+/// History sources are the last group, and only when no root had a match
+/// (a listing shows both). Also returns whether it `collects_all`.
+fn root_groups(segment: &Segment, opts: &SearchOptions) -> (bool, impl Iterator<Item = (PathBuf, Vec<PathBuf>)>) {
+    let mut results = stream::Stream::segment(segment, opts);
+    let (collect_all, list, exact) = (collects_all(opts, results.is_wildcard()), opts.list, results.is_exact());
+    let (mut found, mut done) = (false, false);
+    let mut matches = Vec::new();
+    (collect_all, std::iter::from_fn(move || {
+        if done { return None; }
+        for event in results.by_ref() {
+            match event {
+                stream::Event::Match(c) => {
+                    if c.phase == stream::Phase::Direct && !exact {
+                        found = true;
+                        return Some((c.path.parent().map(Path::to_path_buf).unwrap_or_default(), vec![c.path]));
+                    }
+                    if c.phase == stream::Phase::Source && !list && (found || !matches.is_empty()) { break; }
+                    matches.push(c.path);
+                }
+                stream::Event::RootDone(root) => {
+                    if matches.is_empty() || collect_all { continue; }
+                    found = true;
+                    return Some((root, std::mem::take(&mut matches)));
+                }
             }
         }
+        done = true;
+        (!matches.is_empty()).then(|| (PathBuf::new(), std::mem::take(&mut matches)))
+    }))
+}
 
+/// The main search loop. It iterates through possible search roots (CWD, CDPATH)
+/// and applies the matching phases of `stream::Phase` to each.
+///
+/// Output order is deterministic: roots in `search_roots` order (CWD, then
+/// CDPATH and config roots by priority, then declared order), then phase order
+/// (A, B, C, D) within a root, then name order within a directory, shallowest
+/// level first. Each directory is reported once, keyed by `FileId`, at its first
+/// position; `--links=logical` keys by route instead. History sources come
+/// after every root. `--sort` reorders a listing afterwards.
+pub fn search_cdpath(name: impl AsRef<OsStr>, opts: &SearchOptions) -> Vec<PathBuf> {
+    search_segment(&Segment::name(name.as_ref()), opts)
+}
 
-        // PHASE D: ORIGIN (Search inside the folder)
-        //        if opts.mode != CdMode::Target && (i == 0 || matches.is_empty()) {
-        if opts.mode != CdMode::Target && (i == 0 || matches.is_empty()) {
-            for p in engine.scan_dir(&root, opts) {
-                let d = p.canonicalize().unwrap_or_else(|_| p.clone());
-                if dirs.insert(d) { matches.push(p); }
-            }
-        }
-
-        if !matches.is_empty() {
-            if opts.list || engine.is_wildcard { all_matches.extend(matches); }
-            else if matches.len() == 1 { return matches; }
-            else { report_ambiguity(&root, matches); }
-        }
+/// `search_cdpath` for a parsed query segment, which may be a `re:` pattern.
+fn search_segment(segment: &Segment, opts: &SearchOptions) -> Vec<PathBuf> {
+    // The first root with a match decides; later roots are never read.
+    let (collect_all, mut groups) = root_groups(segment, opts);
+    let Some((root, matches)) = groups.next() else { return Vec::new() };
+    if matches.len() > 1 && !collect_all {
+        if root.as_os_str().is_empty() { report_ambiguity(Path::new("history sources"), matches); }
+        report_ambiguity(&root, matches);
     }
-    all_matches
+    if opts.list { return listing::refine(&segment.spelling().to_string_lossy(), matches, opts); }
+    matches
+}
+
+/// `--exact`, or `--smart-case` with an uppercase letter somewhere in `segment`.
+fn is_case_sensitive(segment: &OsStr, opts: &SearchOptions) -> bool {
+    opts.exact || (opts.smart_case && segment.to_string_lossy().chars().any(char::is_uppercase))
 }
 
 // --- ENGINE MODULES ---
 
 /// Encapsulates all pattern-matching logic.
 /// Centralizing this prevents duplication between Phase B and Phase C scans.
+/// Unicode names are compared through `fold` keys (normalization + full case folding);
+/// names that are not valid Unicode fall back to byte-level rules.
 struct SearchEngine {
-    query: String,
-    query_lower: String,
+    /// The segment being matched, kept to rebuild the engine.
+    segment: Segment,
+    query: OsString,
+    /// Folded (or, when exact, NFC) query text. `None` if the query is not Unicode.
+    text: Option<String>,
     is_wildcard: bool,
     exact: bool,
-    re: Option<regex::Regex>,
+    ignore_accents: bool,
+    /// Glob over folded text, used when both sides are Unicode.
+    text_re: Option<regex::Regex>,
+    /// Glob over raw encoded bytes, used for everything else.
+    re: Option<regex::bytes::Regex>,
 }
 
 impl SearchEngine {
-    /// Constructs the engine and pre-compiles Wildcards into RegEx.
-    /// Uses a "Progressive Transformation" (Perl-style) to convert
-    /// Shell Globs into valid, anchored Regular Expressions.
-    fn new(name: &str, exact: bool) -> Self {
-        let is_wildcard = name.contains('*') || name.contains('?');
-        let re = if is_wildcard {
-            // Start with a mutable string to perform sequential sanitization.
-            let mut pattern = name.to_string();
-
-            // 1. Literal Escape: Ensure dots are treated as dots, not "any character".
-            pattern = pattern.replace('.', "\\.");
+    /// A name or glob; `re:` has no meaning here (see `segment`).
+    fn new(name: impl AsRef<OsStr>, exact: bool) -> Self {
+        Self::segment(&Segment::name(name.as_ref()), exact)
+    }
 
-            // 2. Glob translation: '?' in shell means "one character" (. in regex).
-            pattern = pattern.replace('?', ".");
+    /// An engine for one parsed query segment.
+    fn segment(segment: &Segment, exact: bool) -> Self {
+        Self::build(segment, exact, false)
+    }
 
-            // 3. Glob translation: '*' in shell means "any characters" (.* in regex).
-            pattern = pattern.replace('*', ".*");
+    /// Switches to accent-insensitive keys (`--ignore-accents`), recompiling the glob.
+    fn ignoring_accents(self, on: bool) -> Self {
+        if on == self.ignore_accents { self } else { Self::build(&self.segment, self.exact, on) }
+    }
 
-            // Build the final anchored regex. We anchor with ^ and $ to ensure
-            // the pattern matches the WHOLE directory name, not just a substring.
-            // Note: We do NOT escape backslashes here because this engine
-            // matches against file_name() which is a pure component (no slashes).
-            regex::RegexBuilder::new(&format!("^{}$", pattern))
+    /// Constructs the engine and pre-compiles Wildcards into RegEx.
+    fn build(segment: &Segment, exact: bool, ignore_accents: bool) -> Self {
+        let (name, is_wildcard) = match segment {
+            Segment::Regex(pattern) => return Self::regex(segment, pattern, exact, ignore_accents),
+            Segment::Glob(name) => (name.clone(), true),
+            Segment::Literal(name) => (name.clone(), false),
+            // Navigation is resolved before any search; as a name it only matches itself.
+            Segment::Here | Segment::Up(_) => (segment.spelling(), false),
+        };
+        let bytes = name.as_encoded_bytes();
+        let text = name.to_str().map(|t| {
+            if exact { fold::nfc(t).into_owned() } else { fold::key(t, ignore_accents) }
+        });
+
+        // Build the final anchored regex. We anchor with ^ and $ to ensure
+        // the pattern matches the WHOLE directory name, not just a substring.
+        // Folding already happened, so the text regex is case-sensitive.
+        let text_re = text.as_ref().filter(|_| is_wildcard)
+            .and_then(|t| regex::Regex::new(&format!("^{}$", glob_pattern(t.as_bytes(), "."))).ok());
+        // One glob "character" in raw bytes: a Unicode scalar, or a single invalid byte.
+        let re = if is_wildcard {
+            regex::bytes::RegexBuilder::new(&format!("^{}$", glob_pattern(bytes, r"(?:.|(?-u:[\x80-\xFF]))")))
                 .case_insensitive(!exact)
                 .build()
                 .ok()
         } else { None };
 
         Self {
-            query: name.to_string(),
-            query_lower: name.to_lowercase(),
+            segment: segment.clone(),
+            query: name,
+            text,
             is_wildcard,
             exact,
+            ignore_accents,
+            text_re,
             re,
         }
     }
+
+    /// A `re:` segment. The user's pattern runs on the name as stored (folding
+    /// would rewrite what it matches against), anchored to the whole name.
+    /// Like a glob it can match many names, so it never takes the direct path.
+    fn regex(segment: &Segment, pattern: &str, exact: bool, ignore_accents: bool) -> Self {
+        let re = regex::bytes::RegexBuilder::new(&format!("^(?:{})$", pattern))
+            .case_insensitive(!exact)
+            .build()
+            .ok();
+        Self { segment: segment.clone(), query: segment.spelling(), text: None, is_wildcard: true, exact, ignore_accents, text_re: None, re }
+    }
+
     /// Verifies existence and performs the "Truth Check" for Windows casing.
     fn check_direct(&self, root: &Path) -> Option<PathBuf> {
         let path = root.join(&self.query);
         if !path.is_dir() { return None; }
 
         // Windows Truth Check: canonicalize() returns the path exactly as stored on disk.
-        if self.exact && get_disk_casing(&path) != self.query { return None; }
+        // macOS hands back NFD here, which is still the same name.
+        if self.exact {
+            let disk = get_disk_casing(&path);
+            let same = match (disk.to_str(), &self.text) {
+                (Some(d), Some(q)) => fold::eq_exact(d, q),
+                _ => disk == self.query,
+            };
+            if !same { return None; }
+        }
         Some(path)
     }
 
-    /// Primary matching logic used for both folder names and directory entries.
-    fn matches_path(&self, path: &Path) -> bool {
-        let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
-        if let Some(ref re) = self.re { re.is_match(&name) }
+    /// Matches a single name against the query; `fuzzy` also accepts prefixes.
+    fn matches_name(&self, name: &OsStr, fuzzy: bool) -> bool {
+        if let (Some(q), Some(n)) = (&self.text, name.to_str()) {
+            let n = if self.exact { fold::nfc(n) } else { fold::key(n, self.ignore_accents).into() };
+            return match self.text_re {
+                Some(ref re) => re.is_match(&n),
+                None => n == q.as_str() || (fuzzy && !self.exact && n.starts_with(q.as_str())),
+            };
+        }
+        if let Some(ref re) = self.re { re.is_match(name.as_encoded_bytes()) }
         else if self.exact { name == self.query }
-        else { name.to_lowercase() == self.query_lower }
-    }
-
-    /// High-performance directory crawler.
-    fn scan_dir(&self, root: &Path, opts: &SearchOptions) -> Vec<PathBuf> {
-        let mut found = Vec::new();
-        if let Ok(entries) = std::fs::read_dir(root) {
-            for entry in entries.flatten() {
-                // Ignore files; NCD is strictly for directory navigation.
-                // if !entry.file_type().map(|t| t.is_dir()).unwrap_or(false) { continue; }
-                // don't ignore junctions
-                if !entry.path().is_dir() { continue; }
-                // skip CWD for wildcard
-                let name = entry.file_name().to_string_lossy().into_owned();
-                let is_match = if let Some(ref re) = self.re {
-                    re.is_match(&name)
-                } else if self.exact {
-                    name == self.query
-                } else {
-                    // Supports both 'exact match' and 'starts with' for fast typing.
-                    let nl = name.to_lowercase();
-                    if opts.dir_match == DirMatch::Fuzzy {
-                        nl == self.query_lower || nl.starts_with(&self.query_lower)
-                    } else { // (opts.dir_match == DirMatch::AsIs
-                        nl == self.query_lower
-                    }
-                };
-                if is_match { found.push(entry.path()); }
-            }
+        else {
+            osutil::eq_ignore_case(name, &self.query)
+                || (fuzzy && osutil::starts_with_ignore_case(name, &self.query))
         }
-        found
     }
-}
-
-// --- UTILITIES & SYSTEM HELPERS ---
-fn search_by_fullname(path: &PathBuf, dir: &String, opts: &SearchOptions) -> Vec<PathBuf> {
-    let segment = path.join(dir).canonicalize().ok()
-        .and_then(|canon| canon.file_name().map(|n| n.to_string_lossy().into_owned()))
-        .unwrap_or_else(|| dir.to_string());
-    search_cdpath(&segment, opts)
-}
-
-fn get_drive_components(path: &str) -> (bool, bool, Vec<&str>)  {
-    let mut anchored = false;
-    let mut bare = false;
-    let parts: Vec<&str> = path.split(PATH_SEPARATORS).collect();
 
-    if parts.is_empty() {
-        return (bare, anchored, Vec::new());
+    /// Primary matching logic used for both folder names and directory entries.
+    fn matches_path(&self, path: &Path) -> bool {
+        self.matches_name(path.file_name().unwrap_or_default(), false)
     }
 
-    if path.starts_with(|c| PATH_SEPARATORS.contains(&c)) { anchored = true; }
-
-    if parts[0].len() == 2 &&
-       parts[0].ends_with(DRIVE_SEPARATOR) &&
-       parts[0].chars().next().map_or(false, |c| c.is_alphabetic()) {
-            anchored = true;
+    /// Every match within `depth` levels below `root`, in one call.
+    #[cfg(test)]
+    fn scan(&self, root: &Path, opts: &SearchOptions, depth: usize, ignore: &[SearchEngine]) -> Vec<PathBuf> {
+        let mut walk = stream::Scan::new(root.to_path_buf(), depth);
+        std::iter::from_fn(|| walk.step(self, opts, ignore)).flatten().collect()
     }
+}
 
-    if anchored && (parts.len() == 1 || parts[1].is_empty()) {
-        bare = true;
+/// Translates a shell glob into a regex body. `any` is the pattern for one character.
+/// Text runs are escaped, so only `*` and `?` are special; bytes that are not
+/// UTF-8 become byte literals.
+fn glob_pattern(glob: &[u8], any: &str) -> String {
+    let mut pattern = String::new();
+    for chunk in glob.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                // '?' in shell means "one character", '*' means "any characters".
+                '?' => pattern.push_str(any),
+                '*' => { pattern.push_str(any); pattern.push('*'); }
+                // Everything else is literal, dots included.
+                _ => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+        }
+        for b in chunk.invalid() {
+            pattern.push_str(&format!(r"(?-u:\x{:02X})", b));
+        }
     }
-
-    (bare, anchored, parts)
+    pattern
 }
 
-/// Returns the actual case-preserved name stored by NTFS.
-fn get_disk_casing(path: &Path) -> String {
-    path.canonicalize().ok()
-        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
-        .unwrap_or_default()
+// --- UTILITIES & SYSTEM HELPERS ---
+fn search_by_fullname(path: &Path, segment: &Segment, opts: &SearchOptions) -> Vec<PathBuf> {
+    search_segment(&disk_segment(path, segment), opts)
 }
 
-/// Splits queries using platform-native separators.
-/// Handles both standard paths and root-anchored paths (starting with \ or C:\).
-pub fn split_query(query: Vec<&str>, starts_with_sep: bool, is_anchored: bool) -> (&str, Vec<&str>) {
-    let parts: Vec<&str> = query.into_iter()
-        .filter(|s| !s.is_empty())
-        .collect();
-
-    if parts.is_empty() { return ("", Vec::new()); }
-
-    if starts_with_sep {
-        // Root relative: e.g., "\Projects" -> parts are ["Projects"]
-        ("", parts)
-    } else if is_anchored {
-        // Drive anchored: e.g., "C:\Projects" -> head is "C:", tails are ["Projects"]
-        (parts[0], parts[1..].to_vec())
-    } else {
-        // Naked query: e.g., "Project_Alpha*" -> treat all as segments for search_cdpath
-        ("", parts)
+/// `segment` with a name swapped for its on-disk spelling (see `disk_name`).
+fn disk_segment(path: &Path, segment: &Segment) -> Segment {
+    match segment {
+        Segment::Literal(name) => Segment::Literal(disk_name(path, name)),
+        other => other.clone(),
     }
 }
 
-fn is_ellipsis(head: &str) -> bool {
-    let dir = trim_to_elipses(head);
-    dir.len() > 1 && dir.chars().all(|c| c == '.')
+/// The on-disk spelling of `dir` when it exists under `path`, else `dir` as typed.
+fn disk_name(path: &Path, dir: &OsStr) -> OsString {
+    // canonicalize() would swap a link's name for its target's.
+    let entry = path.join(dir);
+    Some(entry).filter(|e| !links::is_link(e)).and_then(|e| e.canonicalize().ok())
+        .and_then(|canon| canon.file_name().map(OsStr::to_os_string))
+        .unwrap_or_else(|| dir.to_os_string())
 }
 
-fn trim_to_elipses(path: &str) -> String {
-    let is_nav = path.chars().all(|c| c == '.' || c == ' ');
-    let p = if is_nav {
-        path.chars().filter(|&c| c == '.').collect::<String>()
-    } else {
-        path.to_string()
-    };
-    p
+/// Returns the actual case-preserved name stored by NTFS.
+fn get_disk_casing(path: &Path) -> OsString {
+    path.canonicalize().ok()
+        .and_then(|p| p.file_name().map(OsStr::to_os_string))
+        .unwrap_or_default()
 }
 
-/// Handles the "..." syntax.
+/// Handles the "..." syntax: climbs `levels` parents (`...` is 2).
 /// If a tail exists (e.g., ".../src"), it pivots the search root to the calculated parent.
-fn handle_ellipsis(segment: &str, base: PathBuf) -> Vec<PathBuf> {
+fn handle_ellipsis(levels: usize, base: PathBuf) -> Vec<PathBuf> {
     let mut current = if base.is_absolute() { base } else {
         base.canonicalize().unwrap_or_else(|_| env::current_dir().unwrap_or_default().join(base))
     };
 
-    for _ in 0..(segment.len() - 1) {
+    for _ in 0..levels {
         if !current.pop() { break; }
     }
     vec![current]
 }
 
 /// Finds the root of the current drive (e.g., V:\Projects -> V:\) to support root-anchored jumps.
+/// For shares and verbatim paths the root is the whole prefix (e.g. \\server\share).
 fn get_drive_root(path: &Path) -> Option<PathBuf> {
+    if let Some((p, len)) = path.to_str().and_then(prefix::parse) {
+        if p.is_network_or_verbatim() {
+            return path.to_str().map(|s| PathBuf::from(&s[..len]));
+        }
+    }
     path.components()
         .next()
         .map(|c| PathBuf::from(c.as_os_str()))
@@ -521,7 +922,8 @@ fn get_search_roots(mock: &Option<std::ffi::OsString>) -> Vec<PathBuf> {
     let mut roots = Vec::new();
 
     // cwd is expected to be in the list even is it's dup'd in CDPATH.
-    // so leave it in place
+    // so leave it in place: the CDPATH copy still serves target (phase C) matches,
+    // and duplicate results are collapsed by FileId in search_cdpath.
     if let Ok(cwd) = env::current_dir() {
         let _cwd2 = cwd.canonicalize().unwrap_or_else(|_| cwd.clone());
         // if seen.insert(cwd2) { roots.push(cwd.clone()); }
@@ -530,17 +932,41 @@ fn get_search_roots(mock: &Option<std::ffi::OsString>) -> Vec<PathBuf> {
 
     if let Some(cdpath) = env::var_os("CDPATH") {
         for p in env::split_paths(&cdpath) {
-            let p2 = p.clone().canonicalize().unwrap_or_else(|_| p.clone());
-            if seen.insert(p2.clone()) { roots.push(p); }
+            if seen.insert(FileId::of(&p)) { roots.push(p); }
         }
     }
     roots
 }
 
-fn resolve_home() -> Result<(), NcdError> {
+/// `get_search_roots` with the config file's `[root]` settings attached.
+/// Configured roots missing from CDPATH are appended, then priority reorders
+/// everything after the CWD (higher first; ties keep their order).
+fn search_roots(opts: &SearchOptions) -> Vec<config::RootOptions> {
+    let plain = get_search_roots(&opts.mock_path);
+    // A path-locked search has exactly one root and no settings to apply.
+    if opts.mock_path.is_some() || opts.roots.is_empty() {
+        return plain.into_iter().map(config::RootOptions::plain).collect();
+    }
+
+    let configured: Vec<FileId> = opts.roots.iter().map(|r| FileId::of(&r.path)).collect();
+    let mut roots: Vec<config::RootOptions> = plain.into_iter().map(|path| {
+        match configured.iter().position(|c| *c == FileId::of(&path)) {
+            Some(k) => config::RootOptions { path, ..opts.roots[k].clone() },
+            None => config::RootOptions::plain(path),
+        }
+    }).collect();
+
+    let listed: HashSet<FileId> = roots.iter().skip(1).map(|r| FileId::of(&r.path)).collect();
+    roots.extend(opts.roots.iter().zip(&configured)
+        .filter(|(_, c)| !listed.contains(*c))
+        .map(|(r, _)| r.clone()));
+    if roots.len() > 1 { roots[1..].sort_by_key(|r| std::cmp::Reverse(r.priority)); }
+    roots
+}
+
+fn resolve_home() -> Result<PathBuf, NcdError> {
     let home = env::var_os("USERPROFILE").or_else(|| env::var_os("HOME")).map(PathBuf::from);
-    if let Some(path) = home { println!("{}", path.display()); return Ok(()); }
-    Err(NcdError::ResolutionFailed("HOME not found".into()))
+    home.ok_or_else(|| NcdError::ResolutionFailed("HOME not found".into()))
 }
 
 /// Prevents non-deterministic navigation by forcing the user to be more specific.
@@ -556,7 +982,11 @@ fn report_ambiguity(root: &Path, matches: Vec<PathBuf>) -> ! {
 pub enum NcdError {
     InvalidUnicode(std::ffi::OsString),
     ResolutionFailed(String),
+    UnknownBookmark(String),
     ArgError(String),
+    Config(String),
+    Daemon(String),
+    Query(query::ParseError),
     Io(std::io::Error)
 }
 
@@ -566,7 +996,11 @@ impl fmt::Display for NcdError {
         match self {
             Self::InvalidUnicode(os) => write!(f, "Invalid Unicode: {:?}", os),
             Self::ResolutionFailed(q) => write!(f, "Could not resolve \"{}\"", q),
+            Self::UnknownBookmark(name) => write!(f, "Unknown bookmark :{} (nor a known folder or workspace member)", name),
             Self::ArgError(msg) => write!(f, "Arg error: {}", msg),
+            Self::Config(msg) => write!(f, "Config error: {}", msg),
+            Self::Daemon(msg) => write!(f, "Daemon error: {}", msg),
+            Self::Query(e) => write!(f, "Invalid query at {}", e),
             Self::Io(err) => write!(f, "IO error: {}", err),
         }
     }
@@ -578,24 +1012,82 @@ NCD: High-Speed Directory Navigator (Fortress Edition)
 
 USAGE:
     ncd [OPTIONS] <PATH>
+    ncd daemon [--status | --stop]
+    ncd import --from=<z|fasd|autojump|zoxide|csv> <FILE>
+    ncd export [--format=<json|csv>]
 
 ARGUMENTS:
     <PATH>
         ...           Jump up parent directories (3 dots = up 2 levels, no limit).
         -             Jump to the previous directory (OLDPWD).
         ~             Jump to home directory.
+        ~/src         Start from home (ncd -- -/src starts from OLDPWD).
+        :work/api     Start from the bookmark 'work' (bookmark.work in the config).
+        :downloads    Well-known folders: desktop, downloads, documents, config, cache,
+                      data, temp, cargo, rustup.
+        crate:serde@1 The unpacked source of a Cargo dependency (Cargo.lock picks the version).
+        ws:api/src    The workspace member whose package is named 'api' (Cargo, npm, pnpm, go.work).
+        git:acme/api  The clone below CWD or CDPATH whose origin is acme/api (partial names work).
+        wt:feature/x  The worktree of this repository with that branch or name.
+        sub:vendor    The submodule of this repository with that name or path.
         project       Search for a project directory in CWD then CDPATH.
         project/src   Search for 'project' then append 'src'.
         proj*         Wildcard search (Matches 'Project_Alpha', etc).
         *go*          Glob match (Matches '.cargo', 'Cargo', gopher, etc).
+        re:v\d+       Regex segment, matched against whole names; runs to the next '/'.
+        src/main.rs:12:5  A file jumps to its directory; :line:col is dropped if needed.
 
 OPTIONS:
     -h, --help        Print this help message.
     -q, --quiet       Suppress error messages on resolution failure.
     -e, --exact       Disable case-insensitive fallback (Strict matching).
+    --ignore-accents  Match letters regardless of accents (cafe finds café).
+    --smart-case      Lowercase segments match any case; a segment with an
+                      uppercase letter matches case-sensitively.
+    --no-smart-case   Turn off smart-case set by NCD_SMART_CASE or the config.
     -l, --list        List all matches instead of jumping (Search Engine mode).
+    -1, --first       On ambiguity, jump to the top ranked match.
+    --best            On ambiguity, jump only if the top match clearly wins.
+    --margin=<N>      Score lead required by --best (default: 1000).
+    --frecent         Rank directories you jump to often and lately higher.
+    --recent          Rank recently modified directories higher.
     -#, --glob        Does wildcard matching without using the globs (* and ?).
     --cd=<MODE>       Set search strategy (default mode: origin).
+    --path-style=<S>  Output spelling: native, posix, windows, wsl, cygwin, msys.
+    --links=<POLICY>  Symlinks/junctions: follow (default), logical, physical, skip.
+    --sources=<LIST>  Also search history sources after the roots (see SOURCES).
+    --has=<GLOB>      Only match directories holding an entry that matches GLOB
+                      (a name, any case); applied before ambiguity is judged.
+    -f, --file=<NAME> Find the directory holding file NAME (a glob), in a root or
+                      up to 4 levels below; a query narrows the directories.
+    -p, --mkdir       Create the missing end of a query (plain names only) below
+                      the part that resolves, then jump there.
+    --tmp [PREFIX]    Create a new scratch directory PREFIX-xxxxxx (under NCD_TMP,
+                      the config's tmp, or the temp dir) and jump there.
+    --tmp --clean     Remove scratch directories ncd made that were not modified
+                      for 7 days (--older-than=<T> to change).
+
+LIST CONTROLS (with --list):
+    --sort=<KEY>      Order by name, mtime, depth, root or score.
+    -r, --reverse     Reverse the sort order (root order if no --sort).
+    --newer-than=<T>  Only directories modified within T (90s, 30m, 12h, 7d, 2w).
+    --contains=<FILE> Only directories where FILE exists, spelled as given: no
+                      glob, may be a relative path (src/main.rs). Unlike
+                      --has, it only filters the listing.
+    --limit=<N>       Print at most N entries (stops the search early).
+    --json            Print the listing as JSON (implies --list).
+
+LINK POLICIES:
+    follow            Match and descend through links; print the link path.
+    logical           Like follow, but each link to a directory is its own result.
+    physical          Like follow, but print the resolved target.
+    skip              Ignore links entirely.
+
+SOURCES (comma-separated, or none):
+    recent            Directories ncd jumped to (recorded while enabled).
+    history           cd/pushd/Set-Location targets in bash, zsh and PSReadLine history.
+    zoxide, autojump, z   Those tools' databases, read in place.
+    explorer          Folders in Windows Explorer's Recent items.
 
 MODES:
     origin            Scans INSIDE directories listed in CDPATH. (default, sh style)
@@ -609,6 +1101,15 @@ WILDCARDS:
     match a wildcard, NCD will list them and abort to prevent "FUBAR" jumps.
     Use --list to see all matches without aborting.
 
+RANKING (--first / --best):
+    1. Exact name > case-insensitive > prefix > glob.
+    2. CWD > CDPATH entries, in CDPATH order.
+    3. Shallower > deeper.
+    4. Often and lately jumped to > the rest (only with --frecent).
+    5. Newer > older (only with --recent).
+    Each tier outweighs all the tiers below it. --best jumps only when the
+    winner leads by --margin (NCD_MARGIN, default 1000: one CDPATH step).
+
 ENVIRONMENT VARIABLES:
     CDPATH            Semicolon-separated list of search roots.
                       Default: V:\Projects
@@ -616,6 +1117,27 @@ ENVIRONMENT VARIABLES:
     NCD_MODE          Set default strategy (origin, target, hybrid).
                       Default: origin
 
+    NCD_PATH_STYLE    Default --path-style.
+                      Default: native
+
+    NCD_MARGIN        Default score lead for --best.
+                      Default: 1000
+
+    NCD_SMART_CASE    Enable --smart-case by default (1/0, true/false).
+                      Default: off (or `smart-case` in the config file)
+
+    NCD_CONFIG        Path of the config file.
+                      Default: ~/.config/ncd/ncd.conf (%APPDATA%\ncd\ncd.conf)
+
+    NCD_SOURCES       Default --sources.
+                      Default: none (or `sources` in the config file)
+
+    NCD_STORE         File of ncd's own jump history (the recent source).
+                      Default: ~/.local/share/ncd/history.tsv (%LOCALAPPDATA%\ncd\history.tsv)
+
+    NCD_SOCKET        Address of `ncd daemon` (socket path or pipe name).
+                      Default: $XDG_RUNTIME_DIR/ncd.sock (\\.\pipe\ncd-%USERNAME%)
+
     USERPROFILE/HOME  Used for '~' resolution.
 
     OLDPWD            Maintained by shell; used for '-' resolution.
@@ -635,6 +1157,8 @@ CAVEATS
                            Default CDPATH behaviour is POSIX/Unix (--cd=origin)
 
 Portability: Uses OS-native path separators and environment variables.
+             Queries may use C:\x, C:/x, /c/x, /mnt/c/x or /cygdrive/c/x; output
+             follows --path-style so one binary serves cmd, Git Bash and WSL.
 
 "#;
     eprintln!("{}", help_text.trim());
@@ -0,0 +1,85 @@
+// src/osutil.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Lossless helpers for `OsStr` queries and directory names.
+//!
+//! Names that are not valid Unicode (raw bytes on Unix, unpaired surrogates on
+//! Windows) cannot round-trip through `String`. These helpers work on the
+//! encoded bytes instead. Every split happens on an ASCII byte, which is the
+//! documented condition for rebuilding an `OsStr` from encoded bytes.
+
+use std::ffi::{OsStr, OsString};
+
+fn from_bytes(b: &[u8]) -> &OsStr {
+    // SAFETY: `b` is a sub-slice of an `OsStr`'s encoded bytes, cut only at ASCII
+    // bytes or right after a valid UTF-8 run, so it is itself valid encoded data.
+    unsafe { OsStr::from_encoded_bytes_unchecked(b) }
+}
+
+/// Trims ASCII whitespace from both ends (Unicode whitespace too, when valid UTF-8).
+pub fn trim(s: &OsStr) -> &OsStr {
+    match s.to_str() {
+        Some(t) => OsStr::new(t.trim()),
+        None => from_bytes(s.as_encoded_bytes().trim_ascii()),
+    }
+}
+
+/// Splits on any of the given ASCII separators, keeping empty pieces like `str::split`.
+pub fn split<'a>(s: &'a OsStr, seps: &[char]) -> Vec<&'a OsStr> {
+    s.as_encoded_bytes()
+        .split(|b| seps.iter().any(|&c| c.is_ascii() && c as u8 == *b))
+        .map(from_bytes)
+        .collect()
+}
+
+/// The longest valid UTF-8 prefix. Anchors (`C:`, `\\server\share`) are ASCII,
+/// so they can be detected here even when later components are not Unicode.
+pub fn utf8_lead(s: &OsStr) -> &str {
+    let b = s.as_encoded_bytes();
+    match std::str::from_utf8(b) {
+        Ok(t) => t,
+        Err(e) => std::str::from_utf8(&b[..e.valid_up_to()]).unwrap_or_default(),
+    }
+}
+
+/// Bytes `range` of `s`. Both ends must fall on an ASCII byte (or the ends of
+/// `s`), like every cut made in this module.
+pub fn slice(s: &OsStr, range: std::ops::Range<usize>) -> &OsStr {
+    from_bytes(&s.as_encoded_bytes()[range])
+}
+
+/// Splits `s` after `len` bytes. `len` must not exceed `utf8_lead(s).len()`, so the
+/// cut always follows a valid UTF-8 substring.
+pub fn split_at(s: &OsStr, len: usize) -> (&OsStr, &OsStr) {
+    let b = s.as_encoded_bytes();
+    (from_bytes(&b[..len]), from_bytes(&b[len..]))
+}
+
+/// Case-insensitive equality: full Unicode lowercase when both sides are text,
+/// ASCII-only folding otherwise.
+pub fn eq_ignore_case(a: &OsStr, b: &OsStr) -> bool {
+    match (a.to_str(), b.to_str()) {
+        (Some(x), Some(y)) => x.to_lowercase() == y.to_lowercase(),
+        _ => a.as_encoded_bytes().eq_ignore_ascii_case(b.as_encoded_bytes()),
+    }
+}
+
+/// Case-insensitive `starts_with`, with the same folding rules as `eq_ignore_case`.
+pub fn starts_with_ignore_case(name: &OsStr, prefix: &OsStr) -> bool {
+    match (name.to_str(), prefix.to_str()) {
+        (Some(x), Some(y)) => x.to_lowercase().starts_with(&y.to_lowercase()),
+        _ => {
+            let (n, p) = (name.as_encoded_bytes(), prefix.as_encoded_bytes());
+            n.len() >= p.len() && n[..p.len()].eq_ignore_ascii_case(p)
+        }
+    }
+}
+
+/// Rebuilds a name written out as encoded bytes (a socket, a data file).
+/// Off Unix, only valid UTF-8 is accepted: the encoding is not a stable format.
+pub fn from_vec(bytes: Vec<u8>) -> Option<OsString> {
+    #[cfg(unix)]
+    { Some(std::os::unix::ffi::OsStringExt::from_vec(bytes)) }
+    #[cfg(not(unix))]
+    { String::from_utf8(bytes).ok().map(OsString::from) }
+}
@@ -0,0 +1,157 @@
+// src/pathstyle.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Path spelling translation between Windows and the POSIX layers that run on it.
+//!
+//! The same `C:\src\ncd` directory is spelled four ways depending on the shell:
+//!
+//! | Style    | Spelling          | Shell                       |
+//! | -------- | ----------------- | --------------------------- |
+//! | windows  | `C:\src\ncd`      | cmd, PowerShell             |
+//! | posix    | `C:/src/ncd`      | anything accepting `/`      |
+//! | msys     | `/c/src/ncd`      | Git Bash, MSYS2             |
+//! | wsl      | `/mnt/c/src/ncd`  | WSL                         |
+//! | cygwin   | `/cygdrive/c/src` | Cygwin                      |
+//!
+//! Input in any of these forms is mapped onto the host before searching, and
+//! output is rendered in the style the caller asked for (`--path-style`).
+
+use std::path::Path;
+use crate::NcdError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PathStyle {
+    /// Whatever the host OS uses; no translation.
+    #[default]
+    Native,
+    Posix,
+    Windows,
+    Wsl,
+    Cygwin,
+    Msys,
+}
+
+impl PathStyle {
+    pub fn parse(s: &str) -> Result<Self, NcdError> {
+        match s {
+            "native" => Ok(Self::Native),
+            "posix" => Ok(Self::Posix),
+            "windows" => Ok(Self::Windows),
+            "wsl" => Ok(Self::Wsl),
+            "cygwin" => Ok(Self::Cygwin),
+            "msys" => Ok(Self::Msys),
+            _ => Err(NcdError::ArgError(format!("Invalid path style: {}", s))),
+        }
+    }
+}
+
+/// A path split into an optional drive letter and a `/`-separated remainder.
+#[derive(Debug, PartialEq)]
+pub struct DrivePath<'a> {
+    pub drive: Option<char>,
+    pub rest: &'a str,
+}
+
+/// Recognises any of the supported drive spellings. The remainder keeps its
+/// original separators and never starts with one.
+pub fn split_drive(path: &str) -> DrivePath<'_> {
+    split_with(path, &["/mnt/", "/cygdrive/", "/"])
+}
+
+/// Like `split_drive`, but `/x/..` is left alone: on a POSIX host that is a real
+/// root directory, and MSYS binaries already see native `X:\` paths.
+fn split_host(path: &str) -> DrivePath<'_> {
+    split_with(path, &["/mnt/", "/cygdrive/"])
+}
+
+fn split_with<'a>(path: &'a str, prefixes: &[&str]) -> DrivePath<'a> {
+    let is_sep = |c: char| c == '/' || c == '\\';
+    let letter_at = |s: &str| -> Option<(char, usize)> {
+        let mut chars = s.chars();
+        let c = chars.next().filter(|c| c.is_ascii_alphabetic())?;
+        match chars.next() {
+            None => Some((c, 1)),
+            Some(n) if is_sep(n) => Some((c, 2)),
+            _ => None,
+        }
+    };
+
+    // C:\x, C:/x, C:
+    let b = path.as_bytes();
+    if b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':' && (b.len() == 2 || is_sep(b[2] as char)) {
+        return DrivePath { drive: Some(b[0] as char), rest: path[2..].trim_start_matches(is_sep) };
+    }
+    if !path.starts_with('/') { return DrivePath { drive: None, rest: path }; }
+
+    for prefix in prefixes {
+        if let Some(tail) = path.strip_prefix(prefix) {
+            if let Some((c, n)) = letter_at(tail) {
+                return DrivePath { drive: Some(c), rest: tail[n..].trim_start_matches(is_sep) };
+            }
+        }
+    }
+    DrivePath { drive: None, rest: path }
+}
+
+/// Maps a foreign-style query onto the host so the normal pipeline can anchor it.
+///
+/// Translation only happens when the query's native reading does not exist but
+/// the translated drive root does, so a real `/c` directory on Linux keeps winning.
+/// On Windows `/d/x` is also `\d\x` on the current drive, so the MSYS spelling
+/// only names a drive under an MSYS shell (`MSYSTEM` is set) or with
+/// `--path-style=msys`; `/cygdrive/d` and `/mnt/d` always do.
+pub fn to_host(query: &str, style: PathStyle) -> String {
+    let dp = split_drive(query);
+    let Some(drive) = dp.drive else { return query.to_string() };
+    if Path::new(query).exists() { return query.to_string(); }
+    let msys = style == PathStyle::Msys || std::env::var_os("MSYSTEM").is_some();
+    if cfg!(windows) && !msys && split_host(query).drive.is_none() { return query.to_string(); }
+
+    host_candidates(drive, dp.rest).into_iter()
+        .find(|(root, _)| Path::new(root).is_dir())
+        .map(|(_, full)| full)
+        .unwrap_or_else(|| query.to_string())
+}
+
+/// `(drive root, translated query)` pairs to try, in preference order.
+#[cfg(windows)]
+fn host_candidates(drive: char, rest: &str) -> Vec<(String, String)> {
+    let root = format!("{}:\\", drive.to_ascii_uppercase());
+    vec![(root.clone(), format!("{}{}", root, rest.replace('/', "\\")))]
+}
+
+#[cfg(not(windows))]
+fn host_candidates(drive: char, rest: &str) -> Vec<(String, String)> {
+    let d = drive.to_ascii_lowercase();
+    let rest = rest.replace('\\', "/");
+    // WSL mounts first, then Cygwin, then MSYS-style root mounts.
+    ["/mnt/", "/cygdrive/", "/"].iter()
+        .map(|p| {
+            let root = format!("{}{}", p, d);
+            let full = if rest.is_empty() { root.clone() } else { format!("{}/{}", root, rest) };
+            (root, full)
+        })
+        .collect()
+}
+
+/// Renders a resolved path in the requested style.
+pub fn render(path: &Path, style: PathStyle) -> String {
+    let raw = path.to_string_lossy();
+    if style == PathStyle::Native { return raw.into_owned(); }
+
+    // POSIX spellings have no verbatim form; \\?\UNC\srv\share must become //srv/share.
+    let plain = crate::prefix::strip_verbatim(&raw);
+    let dp = split_host(&plain);
+    let rest = dp.rest.replace('\\', "/");
+    let join = |head: String| if rest.is_empty() { head } else { format!("{}/{}", head.trim_end_matches('/'), rest) };
+    match (dp.drive, style) {
+        (Some(d), PathStyle::Windows) => format!("{}:\\{}", d.to_ascii_uppercase(), rest.replace('/', "\\")),
+        (Some(d), PathStyle::Posix) => join(format!("{}:/", d.to_ascii_uppercase())),
+        (Some(d), PathStyle::Wsl) => join(format!("/mnt/{}", d.to_ascii_lowercase())),
+        (Some(d), PathStyle::Cygwin) => join(format!("/cygdrive/{}", d.to_ascii_lowercase())),
+        (Some(d), PathStyle::Msys) => join(format!("/{}", d.to_ascii_lowercase())),
+        (None, PathStyle::Windows) => rest.replace('/', "\\"),
+        (None, _) => rest,
+        (Some(_), PathStyle::Native) => raw.to_string(),
+    }
+}
@@ -0,0 +1,111 @@
+// src/prefix.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Windows path prefixes: drive, UNC share, verbatim (`\\?\`) and device (`\\.\`).
+//!
+//! `std::path::Prefix` only exists on Windows builds. This parser works on plain
+//! strings so queries are anchored the same way everywhere, and so the output
+//! normaliser can decide which `\\?\` prefixes are safe to drop.
+
+/// Classic `MAX_PATH`. Verbatim paths at or above it keep their `\\?\` prefix,
+/// since that is the only spelling shells can open without long-path support.
+pub const MAX_PATH: usize = 260;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Prefix<'a> {
+    /// `C:`
+    Disk(char),
+    /// `\\server\share`
+    Unc(&'a str, &'a str),
+    /// `\\?\C:`
+    VerbatimDisk(char),
+    /// `\\?\UNC\server\share`
+    VerbatimUnc(&'a str, &'a str),
+    /// Any other verbatim form, e.g. `\\?\Volume{guid}`.
+    Verbatim(&'a str),
+    /// `\\.\COM1`, `\\.\PhysicalDrive0`
+    DeviceNs(&'a str),
+}
+
+impl Prefix<'_> {
+    /// True for the prefixes that carry their own root (everything but `C:`).
+    pub fn is_network_or_verbatim(&self) -> bool {
+        !matches!(self, Prefix::Disk(_))
+    }
+}
+
+/// Separators that may start a UNC/device prefix. `//server` is a UNC share on
+/// Windows but just `/server` on POSIX, so only `\` counts off-Windows.
+fn is_prefix_sep(c: char) -> bool {
+    c == '\\' || (cfg!(windows) && c == '/')
+}
+
+/// Splits off the next component, returning it and the byte offset just past it.
+fn component(s: &str, start: usize, sep: fn(char) -> bool) -> (&str, usize) {
+    let tail = &s[start..];
+    let end = tail.find(sep).unwrap_or(tail.len());
+    (&tail[..end], start + end)
+}
+
+/// Parses the prefix of `path`, returning it and the byte length it covers.
+/// The remainder (`&path[len..]`) starts at the root separator, if any.
+pub fn parse(path: &str) -> Option<(Prefix<'_>, usize)> {
+    let bs = |c: char| c == '\\';
+    let b = path.as_bytes();
+
+    if let Some(tail) = path.strip_prefix(r"\\?\") {
+        let tb = tail.as_bytes();
+        // Bytes, not `tail[..4]`: the tail may start with a multi-byte character.
+        if tb.get(..4).is_some_and(|h| h.eq_ignore_ascii_case(br"UNC\")) {
+            let (server, i) = component(path, 8, bs);
+            let (share, j) = component(path, (i + 1).min(path.len()), bs);
+            return Some((Prefix::VerbatimUnc(server, share), j));
+        }
+        if tb.len() >= 2 && tb[0].is_ascii_alphabetic() && tb[1] == b':' {
+            return Some((Prefix::VerbatimDisk(tb[0] as char), 6));
+        }
+        let (name, i) = component(path, 4, bs);
+        return Some((Prefix::Verbatim(name), i));
+    }
+
+    let mut chars = path.chars();
+    if let (Some(a), Some(b2)) = (chars.next(), chars.next()) {
+        if is_prefix_sep(a) && is_prefix_sep(b2) {
+            let third = path[2..].chars().next()?;
+            if third == '.' && path[3..].starts_with(is_prefix_sep) {
+                let (dev, i) = component(path, 4, is_prefix_sep);
+                return Some((Prefix::DeviceNs(dev), i));
+            }
+            if is_prefix_sep(third) { return None; }
+            let (server, i) = component(path, 2, is_prefix_sep);
+            if i >= path.len() { return Some((Prefix::Unc(server, ""), i)); }
+            let (share, j) = component(path, i + 1, is_prefix_sep);
+            return Some((Prefix::Unc(server, share), j));
+        }
+    }
+
+    if b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':' {
+        return Some((Prefix::Disk(b[0] as char), 2));
+    }
+    None
+}
+
+/// Produces the spelling handed back to the shell.
+///
+/// `\\?\C:\x` becomes `C:\x` and `\\?\UNC\srv\share\x` becomes `\\srv\share\x`,
+/// unless the result would reach `MAX_PATH` (in UTF-16 units, as Windows
+/// counts). Other verbatim and device paths have no non-verbatim spelling and
+/// are printed unchanged.
+pub fn normalize_output(path: &str) -> String {
+    let plain = strip_verbatim(path);
+    if plain.encode_utf16().count() < MAX_PATH { plain } else { path.to_string() }
+}
+
+/// Rewrites verbatim disk/UNC paths to their plain spelling, regardless of length.
+pub fn strip_verbatim(path: &str) -> String {
+    match parse(path) {
+        Some((Prefix::VerbatimDisk(_), _)) => path[4..].to_string(),
+        Some((Prefix::VerbatimUnc(..), _)) => format!(r"\\{}", &path[8..]),
+        _ => path.to_string(),
+    }
+}
@@ -0,0 +1,283 @@
+// src/query.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! The query language: what `ncd <PATH>` accepts, parsed into a `Query`.
+//!
+//! ```text
+//! query    = start [ segment { sep segment } ] ( location | { sep } )
+//! start    = "~" | "-" | ":" name          home, $OLDPWD, a bookmark
+//!          | "crate:" name [ "@" version ] a Cargo dependency's source
+//!          | "ws:" member                  a workspace member, by package name
+//!          | "git:" [ org sep ] repo       a local clone, by its remote URL
+//!          | "wt:" worktree | "sub:" name  a worktree (or its branch), a submodule
+//!          | prefix | drive [ sep ] | sep  \\server\share, C:, the drive's root
+//!          |                               (nothing) search CWD, then CDPATH
+//! segment  = "." | dots | "re:" regex | glob | name
+//! dots     = ".." | "..." | "...." ...     up one level per dot after the first
+//! glob     = a name holding "*" or "?"
+//! location = ":" line [ ":" column ]       as compilers print it
+//! ```
+//!
+//! `sep` is `/`, and `\` on Windows; after a `\\` prefix `\` always separates.
+//! Repeated separators count as one. A `re:` segment runs to the next `/`
+//! only, so `\d` keeps its regex meaning; the regex must match a whole name.
+//! The special starts (`~`, `-`, `:name`) need a separator or the end after
+//! them: `~x` and `-x` are ordinary names, and `./:x` reaches a folder named
+//! `:x`. Whitespace around `.` and dots is ignored; in a name it is kept.
+//! Bookmarks are `bookmark.<name> = <path>` lines in the config file; a
+//! `:name` that is no bookmark may be a well-known folder (`:downloads`, see
+//! `folders`) or a workspace member.
+//!
+//! The last segment may name a file; the jump then lands in the file's
+//! directory. `parse` keeps a location as part of the last name, so a folder
+//! really named `build:2` is found as typed; when the whole text leads
+//! nowhere, the search is retried on `without_location`.
+
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use crate::{osutil, prefix, DOS_SEPARATOR, PATH_SEPARATORS, UNIX_SEPARATOR};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub anchor: Anchor,
+    pub segments: Vec<Segment>,
+}
+
+/// Where the segments start.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anchor {
+    /// No anchor: the first segment is searched in CWD, then CDPATH.
+    Relative,
+    /// A leading separator: the root of the current drive.
+    Root,
+    /// `C:` or `C:\`, as typed.
+    Drive(OsString),
+    /// `\\server\share`, `\\?\C:` and the other Windows prefixes, as typed.
+    Prefix(OsString),
+    /// `~`
+    Home,
+    /// `-`, the previous directory.
+    Previous,
+    /// `:name`
+    Bookmark(String),
+    /// `crate:<name>[@version]`, a Cargo dependency's source (see `cargo`).
+    Crate { name: String, version: Option<String> },
+    /// `ws:<member>`, a workspace member by package name (see `workspace`).
+    Member(String),
+    /// `git:<org>/<repo>`, a local clone by its remote (see `clones`). Holds
+    /// the first part only: how many segments after it still belong to the
+    /// remote is settled against the clones found.
+    Remote(String),
+    /// `wt:<branch-or-name>`, a worktree of the repository around CWD (see `gitrepo`).
+    Worktree(String),
+    /// `sub:<name>`, a submodule of the repository around CWD.
+    Submodule(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// `.` (or a segment of only whitespace).
+    Here,
+    /// `..` is `Up(1)`, `...` is `Up(2)`, and so on.
+    Up(usize),
+    Literal(OsString),
+    Glob(OsString),
+    /// `re:<pattern>`.
+    Regex(String),
+}
+
+/// A query that does not follow the grammar. Columns count characters from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl Segment {
+    /// The kind of segment `text` is. Never fails: `parse` rejects the
+    /// spellings that are mistakes before they get here.
+    pub fn classify(text: &OsStr) -> Self {
+        let trimmed = osutil::trim(text).as_encoded_bytes();
+        if trimmed.is_empty() || trimmed == b"." { return Self::Here; }
+        if trimmed.iter().all(|&b| b == b'.') { return Self::Up(trimmed.len() - 1); }
+        if let Some(pattern) = text.to_str().and_then(|t| t.strip_prefix("re:")) {
+            return Self::Regex(pattern.to_string());
+        }
+        Self::name(text)
+    }
+
+    /// A directory name, which is a glob when it holds `*` or `?`. Dots and
+    /// `re:` mean nothing here: this is for names, not query text.
+    pub fn name(text: &OsStr) -> Self {
+        if text.as_encoded_bytes().iter().any(|&b| b == b'*' || b == b'?') {
+            return Self::Glob(text.to_os_string());
+        }
+        Self::Literal(text.to_os_string())
+    }
+
+    /// The segment written back as query text.
+    pub fn spelling(&self) -> OsString {
+        match self {
+            Self::Here => ".".into(),
+            Self::Up(n) => ".".repeat(n + 1).into(),
+            Self::Literal(s) | Self::Glob(s) => s.clone(),
+            Self::Regex(p) => format!("re:{}", p).into(),
+        }
+    }
+}
+
+/// One segment of query text, read as `parse` reads it (without its checks).
+impl From<&str> for Segment {
+    fn from(text: &str) -> Self { Self::classify(OsStr::new(text)) }
+}
+
+/// A `tag:name` start that takes nothing but a name: tag, what the name is,
+/// and the anchor it makes.
+type Scheme = (&'static str, &'static str, fn(String) -> Anchor);
+
+const SCHEMES: [Scheme; 4] = [
+    ("ws:", "a member name", Anchor::Member),
+    ("git:", "a repository", Anchor::Remote),
+    ("wt:", "a worktree or branch", Anchor::Worktree),
+    ("sub:", "a submodule", Anchor::Submodule),
+];
+
+/// Parses a whole query. Surrounding whitespace is ignored.
+pub fn parse(query: &OsStr) -> Result<Query, ParseError> {
+    let bytes = query.as_encoded_bytes();
+    let lead = osutil::utf8_lead(query);
+    let start = lead.len() - lead.trim_start().len();
+    let body = osutil::trim(osutil::slice(query, start..bytes.len()));
+    let end = start + body.len();
+    let col = |at: usize| String::from_utf8_lossy(&bytes[..at]).chars().count() + 1;
+    let err = |at: usize, message: String| ParseError { column: col(at), message };
+    if body.is_empty() { return Err(err(0, "empty query".into())); }
+
+    let is_sep = |b: u8, seps: &[char]| seps.iter().any(|&c| c.is_ascii() && c as u8 == b);
+    let first = osutil::split(body, PATH_SEPARATORS)[0];
+    let first_len = first.len();
+    let body_lead = osutil::utf8_lead(body);
+
+    // \\server\share, \\?\C:, \\?\UNC\server\share and \\.\device keep their whole
+    // prefix as the anchor, since splitting it would lose the root.
+    let scheme = SCHEMES.iter().find(|(tag, ..)| first.as_encoded_bytes().starts_with(tag.as_bytes()));
+    let (anchor, mut at, seps): (Anchor, usize, &[char]) = match (prefix::parse(body_lead), scheme) {
+        // Prefixed paths are Windows spellings, so '\\' separates even off-Windows.
+        (Some((p, len)), _) if p.is_network_or_verbatim() => {
+            (Anchor::Prefix(osutil::split_at(body, len).0.to_os_string()), start + len, &[DOS_SEPARATOR, UNIX_SEPARATOR, '\\'])
+        }
+        _ if first_len == 0 => (Anchor::Root, start, PATH_SEPARATORS),
+        _ if first == "~" => (Anchor::Home, start + 1, PATH_SEPARATORS),
+        _ if first == "-" => (Anchor::Previous, start + 1, PATH_SEPARATORS),
+        _ if first.as_encoded_bytes().starts_with(b":") => {
+            let name = match first.to_str() {
+                Some(":") => return Err(err(start + 1, "expected a bookmark name after ':'".into())),
+                Some(f) => f[1..].to_string(),
+                None => return Err(err(start + 1, "bookmark names must be valid Unicode".into())),
+            };
+            (Anchor::Bookmark(name), start + first_len, PATH_SEPARATORS)
+        }
+        _ if first.as_encoded_bytes().starts_with(b"crate:") => {
+            let Some(spec) = first.to_str().map(|f| &f["crate:".len()..]) else {
+                return Err(err(start, "crate names must be valid Unicode".into()));
+            };
+            let (name, version) = match spec.split_once('@') {
+                Some((_, "")) => return Err(err(start + first_len, "expected a version after '@'".into())),
+                Some((n, v)) => (n, Some(v.to_string())),
+                None => (spec, None),
+            };
+            if name.is_empty() { return Err(err(start + "crate:".len(), "expected a crate name after 'crate:'".into())); }
+            (Anchor::Crate { name: name.to_string(), version }, start + first_len, PATH_SEPARATORS)
+        }
+        (_, Some(&(tag, what, anchor))) => {
+            let name = match first.to_str() {
+                Some(f) if f.len() == tag.len() => return Err(err(start + tag.len(), format!("expected {} after '{}'", what, tag))),
+                Some(f) => f[tag.len()..].to_string(),
+                None => return Err(err(start, format!("'{}' names must be valid Unicode", tag))),
+            };
+            (anchor(name), start + first_len, PATH_SEPARATORS)
+        }
+        _ if is_drive(first) => {
+            // The separator after the drive is kept: `C:` alone means the drive's current directory.
+            let len = (first_len + 1).min(body.len());
+            (Anchor::Drive(osutil::slice(body, 0..len).to_os_string()), start + len, PATH_SEPARATORS)
+        }
+        _ => (Anchor::Relative, start, PATH_SEPARATORS),
+    };
+
+    let mut segments = Vec::new();
+    while at < end {
+        if is_sep(bytes[at], seps) { at += 1; continue; }
+        let regex = bytes[at..end].starts_with(b"re:");
+        let len = bytes[at..end].iter()
+            .position(|&b| if regex { b == b'/' } else { is_sep(b, seps) })
+            .unwrap_or(end - at);
+        let text = osutil::slice(query, at..at + len);
+        check(text, at, &err)?;
+        segments.push(Segment::classify(text));
+        at += len;
+    }
+    Ok(Query { anchor, segments })
+}
+
+/// The query without a trailing `:line` or `:line:col` (`src/main.rs:12:5`,
+/// as compilers print it), or `None` if it has none.
+pub fn without_location(query: &OsStr) -> Option<&OsStr> {
+    let body = osutil::trim(query);
+    let len = location_len(body.as_encoded_bytes());
+    (len > 0).then(|| osutil::slice(body, 0..body.len() - len))
+}
+
+/// Length of a trailing `:line` or `:line:col`, or 0. Something must be left
+/// in front of it, so `:12` stays a bookmark name.
+fn location_len(b: &[u8]) -> usize {
+    let mut len = 0;
+    for _ in 0..2 {
+        let rest = &b[..b.len() - len];
+        let digits = rest.iter().rev().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 || rest.len() <= digits + 1 || rest[rest.len() - digits - 1] != b':' { break; }
+        len += digits + 1;
+    }
+    len
+}
+
+/// `C:`: a drive letter, which only Windows has.
+fn is_drive(segment: &OsStr) -> bool {
+    let b = segment.as_encoded_bytes();
+    cfg!(windows) && b.len() == 2 && b[0].is_ascii_alphabetic() && b[1] == b':'
+}
+
+/// Rejects segments that `Segment::classify` would read as something the user
+/// did not mean. `at` is the segment's byte offset in the query.
+fn check(text: &OsStr, at: usize, err: &dyn Fn(usize, String) -> ParseError) -> Result<(), ParseError> {
+    let b = text.as_encoded_bytes();
+    if is_drive(text) {
+        return Err(err(at, "a drive can only start the query".into()));
+    }
+    // `. . .` is not `...`: a typo should not climb the tree.
+    let dots = b.iter().filter(|&&c| c == b'.').count();
+    if dots > 1 && b.iter().all(|&c| c == b'.' || c.is_ascii_whitespace()) {
+        let lead = b.len() - b.trim_ascii_start().len();
+        if let Some(gap) = b.trim_ascii().iter().position(u8::is_ascii_whitespace) {
+            return Err(err(at + lead + gap, "spaces between dots; write `...` to go up".into()));
+        }
+    }
+    if b.starts_with(b"re:") {
+        let Some(pattern) = text.to_str().map(|t| &t[3..]) else {
+            return Err(err(at, "a regex must be valid Unicode".into()));
+        };
+        if pattern.is_empty() { return Err(err(at + 3, "empty regex".into())); }
+        if let Err(e) = regex::Regex::new(pattern) {
+            let reason = e.to_string();
+            let reason = reason.lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+            return Err(err(at + 3, format!("invalid regex: {}", reason)));
+        }
+    }
+    Ok(())
+}
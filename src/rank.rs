@@ -0,0 +1,139 @@
+// src/rank.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Candidate ranking used by `--first` and `--best`.
+//!
+//! Every candidate is scored on a tiered scale so that a higher tier always
+//! outweighs any combination of the lower ones:
+//!
+//! | Tier        | Weight | Rule                                          |
+//! | ----------- | ------ | --------------------------------------------- |
+//! | Match kind  | 100000 | exact > case-insensitive > prefix > glob      |
+//! | Root order  | 1000   | CWD, then CDPATH entries by priority/order    |
+//! | Depth       | 100    | shallower below the root wins                 |
+//! | Frecency    | 10     | often and lately jumped to (`--frecent` only) |
+//! | Recency     | 1      | newer mtime wins (`--recent` only)            |
+//!
+//! Frecency comes from ncd's jump history (see `store`), weighted like z:
+//! a visit within the hour counts 4x, within the day 2x, within the week
+//! 0.5x, anything older 0.25x.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::store::{self, Entry};
+use crate::{search_roots, Pick, SearchOptions, PATH_SEPARATORS};
+
+/// Default lead (one root step) the winner needs under `--best`.
+pub const DEFAULT_MARGIN: i64 = ROOT_WEIGHT;
+
+const KIND_WEIGHT: i64 = 100_000;
+const ROOT_WEIGHT: i64 = 1_000;
+const DEPTH_WEIGHT: i64 = 100;
+const FRECENCY_WEIGHT: i64 = 10;
+const TIER_CAP: i64 = 9;
+
+/// How closely a candidate's name matches the final query segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchKind {
+    Glob,
+    Prefix,
+    CaseInsensitive,
+    Exact,
+}
+
+/// A scored candidate. `root` is the index into the search roots (CWD is 0).
+#[derive(Debug, Clone)]
+pub struct Ranked {
+    pub path: PathBuf,
+    pub score: i64,
+    pub root: usize,
+    pub depth: usize,
+}
+
+/// Classifies `name` against a single query segment.
+pub fn classify(name: &str, segment: &str) -> MatchKind {
+    if segment.contains(['*', '?']) { return MatchKind::Glob; }
+    if crate::fold::eq_exact(name, segment) { return MatchKind::Exact; }
+    let (nl, sl) = (crate::fold::key(name, false), crate::fold::key(segment, false));
+    if nl == sl { MatchKind::CaseInsensitive }
+    else if nl.starts_with(&sl) { MatchKind::Prefix }
+    else { MatchKind::Glob }
+}
+
+/// Scores and sorts candidates, best first. Equal scores keep discovery order.
+pub fn rank_candidates(query: &str, paths: Vec<PathBuf>, opts: &SearchOptions) -> Vec<Ranked> {
+    let segment = query.split(PATH_SEPARATORS).rfind(|s| !s.trim().is_empty()).unwrap_or("").trim();
+    let roots: Vec<PathBuf> = search_roots(opts).into_iter()
+        .map(|r| r.path.canonicalize().unwrap_or(r.path))
+        .collect();
+    let now = SystemTime::now();
+    let history = if opts.rank_frecency { store::load() } else { Vec::new() };
+
+    let mut ranked: Vec<Ranked> = paths.into_iter().map(|path| {
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let kind = classify(&name, segment);
+        let (root, depth) = locate(&path, &roots);
+
+        let mut score = (kind as i64 + 1) * KIND_WEIGHT
+            - (root as i64).min(TIER_CAP * 10) * ROOT_WEIGHT
+            - (depth as i64).min(TIER_CAP) * DEPTH_WEIGHT;
+        if opts.rank_frecency { score += frecency(&path, &history, now) * FRECENCY_WEIGHT; }
+        if opts.rank_mtime { score += recency(&path, now); }
+
+        Ranked { path, score, root, depth }
+    }).collect();
+
+    ranked.sort_by_key(|r| std::cmp::Reverse(r.score));
+    ranked
+}
+
+/// Resolves an ambiguous result set according to `opts.pick`.
+/// Returns the full (ranked) set as the error when no winner may be chosen.
+pub fn pick_candidate(query: &str, paths: Vec<PathBuf>, opts: &SearchOptions) -> Result<PathBuf, Vec<PathBuf>> {
+    if opts.pick == Pick::Strict || paths.len() < 2 {
+        return if paths.len() == 1 { Ok(paths.into_iter().next().unwrap()) } else { Err(paths) };
+    }
+
+    let ranked = rank_candidates(query, paths, opts);
+    let lead = ranked[0].score - ranked[1].score;
+    if opts.pick == Pick::First || lead >= opts.margin.max(1) {
+        return Ok(ranked.into_iter().next().unwrap().path);
+    }
+    Err(ranked.into_iter().map(|r| r.path).collect())
+}
+
+/// Finds which search root a candidate lives under and how far below it.
+fn locate(path: &Path, roots: &[PathBuf]) -> (usize, usize) {
+    let canon = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    roots.iter().enumerate()
+        .filter_map(|(i, r)| canon.strip_prefix(r).ok().map(|rest| (i, rest.components().count())))
+        .min_by_key(|&(_, depth)| depth)
+        .unwrap_or((roots.len(), canon.components().count()))
+}
+
+/// 0..=9 bonus: the base-2 log of the directory's frecency in `history`.
+pub fn frecency(path: &Path, history: &[Entry], now: SystemTime) -> i64 {
+    let canon = path.canonicalize().ok();
+    let Some(e) = history.iter().find(|e| e.path == path || Some(&e.path) == canon.as_ref()) else { return 0 };
+    let now = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let weight = match now.saturating_sub(e.last) {
+        age if age < 3600 => 4.0,
+        age if age < 86_400 => 2.0,
+        age if age < 7 * 86_400 => 0.5,
+        _ => 0.25,
+    };
+    ((e.rank * weight + 1.0).log2() as i64).clamp(0, TIER_CAP)
+}
+
+/// 0..=9 bonus: modified within the hour scores 9, then decays per bracket.
+fn recency(path: &Path, now: SystemTime) -> i64 {
+    const BRACKETS: [u64; 9] = [3600, 86_400, 3 * 86_400, 7 * 86_400, 14 * 86_400,
+        30 * 86_400, 90 * 86_400, 180 * 86_400, 365 * 86_400];
+    let age = path.metadata().and_then(|m| m.modified()).ok()
+        .and_then(|t| now.duration_since(t).ok())
+        .map(|d| d.as_secs());
+    match age {
+        Some(secs) => TIER_CAP - BRACKETS.iter().position(|&b| secs < b).unwrap_or(9) as i64,
+        None => 0,
+    }
+}
@@ -0,0 +1,99 @@
+// src/scratch.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! `ncd --tmp [prefix]`: a fresh scratch directory to jump into.
+//!
+//! Scratch directories are `<prefix>-<random>` (prefix `scratch` by default)
+//! under `$NCD_TMP`, else the config file's `tmp = <dir>`, else `ncd` in the
+//! system temp directory. Each holds a `MARKER` file, and `--tmp --clean`
+//! removes only directories of the base that have one, so nothing ncd did
+//! not create is ever deleted. A directory is stale once nothing in it has
+//! been modified for `--older-than` (7 days by default): the newest mtime
+//! anywhere in the tree counts, since editing a file leaves its directory's
+//! own mtime alone.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::config::{self, Config};
+use crate::pathstyle::PathStyle;
+use crate::NcdError;
+
+/// Marks a directory as ncd's to delete.
+pub const MARKER: &str = ".ncd-tmp";
+
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(7 * 86_400);
+
+/// `--tmp`, `--clean` and `--older-than`.
+#[derive(Debug, Clone, Default)]
+pub struct TmpOptions {
+    pub on: bool,
+    pub clean: bool,
+    pub older_than: Option<Duration>,
+}
+
+/// Runs `--tmp`: prints the new directory, or cleans up with `--clean`.
+pub fn run(opts: &TmpOptions, prefix: Option<&std::ffi::OsStr>, cfg: &Config, style: PathStyle) -> Result<(), NcdError> {
+    let base = base(cfg);
+    if opts.clean {
+        let removed = clean(&base, opts.older_than.unwrap_or(DEFAULT_MAX_AGE))?;
+        // stderr: the shell wrapper jumps to whatever is on stdout.
+        eprintln!("Removed {} scratch directories from {}", removed.len(), base.display());
+        return Ok(());
+    }
+    if opts.older_than.is_some() { return Err(NcdError::ArgError("--older-than needs --clean".into())); }
+    let prefix = match prefix.map(|p| p.to_str()) {
+        None => "scratch",
+        Some(Some(p)) if !p.is_empty() && !p.contains(['/', '\\']) => p,
+        Some(_) => return Err(NcdError::ArgError("The --tmp prefix must be a plain name".into())),
+    };
+    crate::emit(&create(&base, prefix)?, style);
+    Ok(())
+}
+
+/// Where scratch directories are made.
+pub fn base(cfg: &Config) -> PathBuf {
+    env::var_os("NCD_TMP").map(PathBuf::from)
+        .or_else(|| cfg.get("tmp").map(config::expand_home))
+        .unwrap_or_else(|| env::temp_dir().join("ncd"))
+}
+
+/// A new, marked `<prefix>-<random>` directory under `base`.
+pub fn create(base: &Path, prefix: &str) -> Result<PathBuf, NcdError> {
+    std::fs::create_dir_all(base).map_err(NcdError::Io)?;
+    let dir = tempfile::Builder::new().prefix(&format!("{}-", prefix)).tempdir_in(base).map_err(NcdError::Io)?;
+    std::fs::write(dir.path().join(MARKER), b"").map_err(NcdError::Io)?;
+    Ok(dir.keep())
+}
+
+/// Removes the marked directories of `base` where nothing was modified for
+/// `max_age`, and returns them. Links and unmarked directories are left alone.
+pub fn clean(base: &Path, max_age: Duration) -> Result<Vec<PathBuf>, NcdError> {
+    let Ok(entries) = std::fs::read_dir(base) else { return Ok(Vec::new()) };
+    let now = SystemTime::now();
+    let mut removed = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(meta) = std::fs::symlink_metadata(entry.path()) else { continue };
+        if !meta.is_dir() { continue; }
+        let marked = std::fs::symlink_metadata(entry.path().join(MARKER)).is_ok_and(|m| m.is_file());
+        let age = newest(&entry.path(), &meta).and_then(|m| now.duration_since(m).ok()).unwrap_or_default();
+        if !marked || age < max_age { continue; }
+        std::fs::remove_dir_all(entry.path()).map_err(NcdError::Io)?;
+        removed.push(entry.path());
+    }
+    removed.sort();
+    Ok(removed)
+}
+
+/// The latest mtime of `dir` and everything below it. Links are not
+/// followed; an unreadable entry makes the whole tree count as fresh.
+fn newest(dir: &Path, meta: &std::fs::Metadata) -> Option<SystemTime> {
+    let mut latest = meta.modified().ok()?;
+    if !meta.is_dir() { return Some(latest); }
+    for entry in std::fs::read_dir(dir).ok()? {
+        let path = entry.ok()?.path();
+        let meta = std::fs::symlink_metadata(&path).ok()?;
+        latest = latest.max(newest(&path, &meta)?);
+    }
+    Some(latest)
+}
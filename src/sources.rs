@@ -0,0 +1,264 @@
+// src/sources.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Candidates from outside the search roots (`--sources`).
+//!
+//! A source is a list of directories the user has been to. Its entries are
+//! matched by name like scan results, but only after every root, so a live
+//! match always ranks first. Built-in sources:
+//!
+//! | Source     | Reads                                                        |
+//! | ---------- | ------------------------------------------------------------ |
+//! | `recent`   | ncd's own jump history (see `store`); jumps are recorded     |
+//! | `history`  | `cd`/`pushd`/`Set-Location` lines in bash, zsh, PSReadLine   |
+//! | `zoxide`   | `$_ZO_DATA_DIR/db.zo`                                        |
+//! | `autojump` | `autojump.txt` in the autojump data directory                |
+//! | `z`        | `$_Z_DATA` or `~/.z`                                         |
+//! | `explorer` | folder shortcuts in `%APPDATA%\Microsoft\Windows\Recent`    |
+//!
+//! History files only yield absolute (or `~/`) targets: a relative `cd`
+//! depends on a working directory that is no longer known. Explorer's Recent
+//! items are `.lnk` shell links; only those whose target is a folder count,
+//! newest first.
+
+use std::env;
+use std::path::PathBuf;
+use crate::store::{self, Entry};
+use crate::{resolve_home, NcdError};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Recent,
+    History,
+    Zoxide,
+    Autojump,
+    Z,
+    Explorer,
+}
+
+impl Source {
+    pub fn parse(s: &str) -> Result<Self, NcdError> {
+        match s {
+            "recent" => Ok(Self::Recent),
+            "history" => Ok(Self::History),
+            "zoxide" => Ok(Self::Zoxide),
+            "autojump" => Ok(Self::Autojump),
+            "z" => Ok(Self::Z),
+            "explorer" => Ok(Self::Explorer),
+            _ => Err(NcdError::ArgError(format!("Invalid source: {}", s))),
+        }
+    }
+
+    /// A comma-separated list; `none` (or nothing) turns sources off.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, NcdError> {
+        s.split(',').map(str::trim).filter(|s| !s.is_empty() && *s != "none").map(Self::parse).collect()
+    }
+
+    /// The source's directories, best first. Sources that are not installed are empty.
+    pub fn entries(self) -> Vec<PathBuf> {
+        let by_rank = |mut e: Vec<Entry>| {
+            e.sort_by(|a, b| b.rank.total_cmp(&a.rank).then(b.last.cmp(&a.last)));
+            e.into_iter().map(|e| e.path).collect()
+        };
+        match self {
+            Self::Recent => store::load().into_iter().map(|e| e.path).collect(),
+            Self::History => history_files().iter().flat_map(|f| {
+                std::fs::read(f).map(|b| history_dirs(&String::from_utf8_lossy(&b))).unwrap_or_default()
+            }).collect(),
+            Self::Zoxide => by_rank(zoxide_file().and_then(|f| std::fs::read(f).ok()).map(|b| parse_zoxide(&b)).unwrap_or_default()),
+            Self::Autojump => by_rank(read_text(autojump_file()).map(|t| parse_autojump(&t)).unwrap_or_default()),
+            Self::Z => by_rank(read_text(z_file()).map(|t| parse_z(&t)).unwrap_or_default()),
+            Self::Explorer => explorer_links().iter().filter_map(|f| parse_lnk(&std::fs::read(f).ok()?)).collect(),
+        }
+    }
+}
+
+/// Directories from every source, in source order, for `search_cdpath`.
+pub fn candidates(sources: &[Source]) -> Vec<PathBuf> {
+    sources.iter().flat_map(|s| s.entries()).collect()
+}
+
+fn read_text(file: Option<PathBuf>) -> Option<String> {
+    std::fs::read(file?).ok().map(|b| String::from_utf8_lossy(&b).into_owned())
+}
+
+fn home() -> Option<PathBuf> { resolve_home().ok() }
+
+/// `%LOCALAPPDATA%`, else `$XDG_DATA_HOME` or `~/.local/share`.
+pub fn data_dir() -> Option<PathBuf> {
+    if cfg!(windows) { return env::var_os("LOCALAPPDATA").map(PathBuf::from); }
+    env::var_os("XDG_DATA_HOME").map(PathBuf::from).or_else(|| home().map(|h| h.join(".local/share")))
+}
+
+fn history_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = env::var_os("HISTFILE").map(PathBuf::from).into_iter().collect();
+    if let Some(h) = home() {
+        files.push(h.join(".bash_history"));
+        files.push(h.join(".zsh_history"));
+    }
+    // PSReadLine keeps its history next to the module's other data.
+    let psreadline = if cfg!(windows) {
+        env::var_os("APPDATA").map(|a| PathBuf::from(a).join(r"Microsoft\Windows\PowerShell\PSReadLine"))
+    } else {
+        data_dir().map(|d| d.join("powershell/PSReadLine"))
+    };
+    files.extend(psreadline.map(|d| d.join("ConsoleHost_history.txt")));
+    let mut unique = Vec::new();
+    for f in files { if !unique.contains(&f) { unique.push(f); } }
+    unique
+}
+
+fn zoxide_file() -> Option<PathBuf> {
+    env::var_os("_ZO_DATA_DIR").map(PathBuf::from)
+        .or_else(|| data_dir().map(|d| d.join("zoxide")))
+        .map(|d| d.join("db.zo"))
+}
+
+fn autojump_file() -> Option<PathBuf> {
+    let dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(|a| PathBuf::from(a).join("autojump"))
+    } else if cfg!(target_os = "macos") {
+        home().map(|h| h.join("Library/autojump"))
+    } else {
+        data_dir().map(|d| d.join("autojump"))
+    };
+    dir.map(|d| d.join("autojump.txt"))
+}
+
+fn z_file() -> Option<PathBuf> {
+    env::var_os("_Z_DATA").map(PathBuf::from).or_else(|| home().map(|h| h.join(".z")))
+}
+
+/// The `.lnk` files of Explorer's Recent items, most recently used first.
+fn explorer_links() -> Vec<PathBuf> {
+    let Some(dir) = env::var_os("APPDATA").map(|a| PathBuf::from(a).join(r"Microsoft\Windows\Recent")) else { return Vec::new() };
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut links: Vec<(std::time::SystemTime, PathBuf)> = entries.flatten()
+        .filter(|e| e.path().extension().is_some_and(|x| x.eq_ignore_ascii_case("lnk")))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    links.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+    links.into_iter().map(|(_, p)| p).collect()
+}
+
+/// The target of a shell link (MS-SHLLINK) when it is a folder: the
+/// LinkInfo's local base path, or its network share, plus the common suffix.
+pub fn parse_lnk(b: &[u8]) -> Option<PathBuf> {
+    const HAS_ID_LIST: u32 = 0x1;
+    const HAS_LINK_INFO: u32 = 0x2;
+    const DIRECTORY: u32 = 0x10;
+    let u16_at = |at: usize| Some(u16::from_le_bytes(b.get(at..at + 2)?.try_into().ok()?));
+    let u32_at = |at: usize| Some(u32::from_le_bytes(b.get(at..at + 4)?.try_into().ok()?));
+    let offset = |at: usize| usize::try_from(u32_at(at)?).ok();
+    // NUL-terminated strings, in the ANSI code page (read as UTF-8) or UTF-16.
+    let ansi = |at: usize| {
+        let s = b.get(at..)?;
+        Some(String::from_utf8_lossy(&s[..s.iter().position(|&c| c == 0)?]).into_owned())
+    };
+    let wide = |at: usize| {
+        let units: Vec<u16> = (at..).step_by(2).map_while(|i| u16_at(i).filter(|&u| u != 0)).collect();
+        Some(String::from_utf16_lossy(&units))
+    };
+
+    if u32_at(0)? != 0x4C { return None; }
+    let flags = u32_at(0x14)?;
+    if u32_at(0x18)? & DIRECTORY == 0 || flags & HAS_LINK_INFO == 0 { return None; }
+    let mut at = 0x4C;
+    if flags & HAS_ID_LIST != 0 { at += 2 + usize::from(u16_at(at)?); }
+
+    // LinkInfo: the Unicode offsets are only there in a 0x24-byte header.
+    let unicode = offset(at + 4)? >= 0x24;
+    let info = u32_at(at + 8)?;
+    let suffix = if unicode { wide(at + offset(at + 0x20)?)? } else { ansi(at + offset(at + 0x18)?)? };
+    let base = if info & 1 != 0 {
+        if unicode { wide(at + offset(at + 0x1C)?)? } else { ansi(at + offset(at + 0x10)?)? }
+    } else if info & 2 != 0 {
+        let net = at + offset(at + 0x14)?;
+        let share = ansi(net + offset(net + 8)?)?;
+        if suffix.is_empty() { share } else { share + "\\" }
+    } else {
+        return None;
+    };
+    (!base.is_empty()).then(|| PathBuf::from(base + &suffix))
+}
+
+/// `cd` targets in a shell history, most recent first, each once.
+/// Understands zsh's `: <time>:<secs>;<cmd>` lines and PowerShell's cmdlets.
+pub fn history_dirs(text: &str) -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = Vec::new();
+    for line in text.lines().rev() {
+        let cmd = match line.strip_prefix(": ").and_then(|l| l.split_once(';')) {
+            Some((_, cmd)) => cmd,
+            None => line,
+        };
+        // Only the first command of a chain is looked at.
+        let cmd = cmd.split(['&', '|', ';']).next().unwrap_or("").trim();
+        let Some((verb, arg)) = cmd.split_once(char::is_whitespace) else { continue };
+        let verb = verb.to_ascii_lowercase();
+        if !["cd", "pushd", "chdir", "sl", "set-location", "push-location"].contains(&verb.as_str()) { continue; }
+        let arg = arg.trim();
+        let arg = arg.strip_prefix("-LiteralPath ").or_else(|| arg.strip_prefix("-Path ")).unwrap_or(arg).trim();
+        let arg = arg.trim_matches(['"', '\'']);
+        let path = match arg.strip_prefix("~/").or_else(|| arg.strip_prefix("~\\")) {
+            Some(rest) => match home() { Some(h) => h.join(rest), None => continue },
+            None => PathBuf::from(arg),
+        };
+        if path.is_absolute() && !out.contains(&path) { out.push(path); }
+    }
+    out
+}
+
+/// z and fasd: `path|rank|time` per line.
+pub fn parse_z(text: &str) -> Vec<Entry> {
+    text.lines().filter_map(|line| {
+        // The path may itself contain `|`, so split from the right.
+        let mut fields = line.trim_end().rsplitn(3, '|');
+        let last = fields.next()?.trim().parse().ok()?;
+        let rank = fields.next()?.trim().parse().ok()?;
+        let path = fields.next().filter(|p| !p.is_empty())?;
+        Some(Entry { path: PathBuf::from(path), rank, last })
+    }).collect()
+}
+
+/// autojump: `weight<TAB>path` per line. autojump keeps no visit times.
+pub fn parse_autojump(text: &str) -> Vec<Entry> {
+    text.lines().filter_map(|line| {
+        let (weight, path) = line.trim_end_matches('\r').split_once('\t')?;
+        let rank = weight.trim().parse().ok()?;
+        Some(Entry { path: PathBuf::from(path), rank, last: 0 })
+    }).collect()
+}
+
+/// zoxide's `db.zo`: a bincode `u32` version (3), then a `u64` count of
+/// `{ path: u64 length + UTF-8, rank: f64, last_accessed: u64 }` records,
+/// all little-endian. Anything else yields no entries.
+pub fn parse_zoxide(bytes: &[u8]) -> Vec<Entry> {
+    fn take<'a>(b: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+        if b.len() < n { return None; }
+        let (head, tail) = b.split_at(n);
+        *b = tail;
+        Some(head)
+    }
+    fn u64_le(b: &mut &[u8]) -> Option<u64> { Some(u64::from_le_bytes(take(b, 8)?.try_into().ok()?)) }
+
+    let mut b = bytes;
+    let Some(version) = take(&mut b, 4) else { return Vec::new() };
+    if version != 3u32.to_le_bytes() { return Vec::new(); }
+    let mut entries = Vec::new();
+    let count = u64_le(&mut b).unwrap_or(0);
+    for _ in 0..count {
+        let record = (|| {
+            let len = usize::try_from(u64_le(&mut b)?).ok()?;
+            let path = std::str::from_utf8(take(&mut b, len)?).ok()?.to_string();
+            let rank = f64::from_bits(u64_le(&mut b)?);
+            let last = u64_le(&mut b)?;
+            Some(Entry { path: PathBuf::from(path), rank, last })
+        })();
+        match record {
+            Some(e) => entries.push(e),
+            None => break,
+        }
+    }
+    entries
+}
+
@@ -0,0 +1,111 @@
+// src/store.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! ncd's own jump history (the `recent` source).
+//!
+//! One `rank<TAB>last-access<TAB>path` line per directory, at `$NCD_STORE`,
+//! else `$XDG_DATA_HOME/ncd/history.tsv` (`~/.local/share/ncd/history.tsv`)
+//! or `%LOCALAPPDATA%\ncd\history.tsv`. Every jump adds 1 to its directory's
+//! rank; once the ranks add up to `MAX_TOTAL` they are all aged by 10% and
+//! entries below 1 are dropped, the same scheme z uses.
+
+use std::env;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::osutil;
+
+const MAX_TOTAL: f64 = 9000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub path: PathBuf,
+    /// Visit weight; higher is more frequent.
+    pub rank: f64,
+    /// Last visit, in seconds since the Unix epoch.
+    pub last: u64,
+}
+
+/// Location of the store (it may not exist).
+pub fn path() -> Option<PathBuf> {
+    if let Some(p) = env::var_os("NCD_STORE") { return Some(PathBuf::from(p)); }
+    let base = if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))
+    };
+    base.map(|b| b.join("ncd").join("history.tsv"))
+}
+
+/// The stored entries, most used first. A missing or unreadable store is empty.
+pub fn load() -> Vec<Entry> {
+    let mut entries = path().and_then(|p| std::fs::read(p).ok()).map(|b| parse(&b)).unwrap_or_default();
+    entries.sort_by(|a, b| b.rank.total_cmp(&a.rank).then(b.last.cmp(&a.last)));
+    entries
+}
+
+/// Parses the store format. Malformed lines are skipped.
+pub fn parse(bytes: &[u8]) -> Vec<Entry> {
+    bytes.split(|&b| b == b'\n').filter_map(|line| {
+        let mut fields = line.splitn(3, |&b| b == b'\t');
+        let rank = std::str::from_utf8(fields.next()?).ok()?.trim().parse().ok()?;
+        let last = std::str::from_utf8(fields.next()?).ok()?.trim().parse().ok()?;
+        let path = fields.next()?;
+        let path = osutil::from_vec(path.strip_suffix(b"\r").unwrap_or(path).to_vec())?;
+        Some(Entry { path: PathBuf::from(path), rank, last })
+    }).collect()
+}
+
+/// The store format for `entries`. Paths containing a newline cannot be stored.
+pub fn render(entries: &[Entry]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for e in entries {
+        let path = e.path.as_os_str().as_encoded_bytes();
+        if path.contains(&b'\n') { continue; }
+        out.extend(format!("{}\t{}\t", e.rank, e.last).into_bytes());
+        out.extend_from_slice(path);
+        out.push(b'\n');
+    }
+    out
+}
+
+/// Replaces the store. Written to a temporary file first, so a crash never
+/// leaves half a store behind.
+pub fn save(entries: &[Entry]) -> io::Result<()> {
+    let path = path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory (set NCD_STORE)"))?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir)?;
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(&render(entries))?;
+    tmp.persist(&path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Adds `incoming` to `entries`: ranks of the same directory add up and the
+/// later visit wins. Returns how many directories were new.
+pub fn merge(entries: &mut Vec<Entry>, incoming: Vec<Entry>) -> usize {
+    let mut added = 0;
+    for new in incoming {
+        match entries.iter_mut().find(|e| e.path == new.path) {
+            Some(e) => { e.rank += new.rank; e.last = e.last.max(new.last); }
+            None => { entries.push(new); added += 1; }
+        }
+    }
+    age(entries);
+    added
+}
+
+/// Records a visit to `dir`.
+pub fn record(dir: &Path) -> io::Result<()> {
+    let mut entries = load();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    merge(&mut entries, vec![Entry { path: dir.to_path_buf(), rank: 1.0, last: now }]);
+    save(&entries)
+}
+
+fn age(entries: &mut Vec<Entry>) {
+    if entries.iter().map(|e| e.rank).sum::<f64>() <= MAX_TOTAL { return; }
+    for e in entries.iter_mut() { e.rank *= 0.9; }
+    entries.retain(|e| e.rank >= 1.0);
+}
@@ -0,0 +1,339 @@
+// src/stream.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Incremental search: results as each root and phase finds them.
+//!
+//! `Stream` is a lazy iterator over the same phases `search_cdpath` runs, so
+//! stopping early (a unique match, a `--limit`, a picker that got its answer)
+//! skips the remaining filesystem work. `spawn` runs a stream on a worker
+//! thread and hands events over a channel; `Cancel` stops it from any thread.
+//!
+//! Events arrive in the documented result order (see `search_cdpath`), with a
+//! `RootDone` marker after each root so callers can apply per-root rules.
+
+use std::collections::{HashSet, VecDeque};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use crate::config::RootOptions;
+use crate::{daemon, sources};
+use crate::identity::FileId;
+use crate::links::{self, LinkPolicy};
+use crate::query::Segment;
+use crate::{is_case_sensitive, search_roots, CdMode, DirMatch, SearchEngine, SearchOptions};
+
+/// Which search phase produced a candidate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// A: the query named an existing child of the root.
+    Direct,
+    /// B: wildcard scan of the CWD.
+    CwdGlob,
+    /// C: the root itself is the match (bookmark style).
+    Target,
+    /// D: scan below the root.
+    Origin,
+    /// After every root: an entry of a history source (`--sources`).
+    Source,
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub path: PathBuf,
+    pub phase: Phase,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Match(Candidate),
+    /// Every candidate from this root has been yielded. The sources, searched
+    /// after the last root, end with an empty path.
+    RootDone(PathBuf),
+}
+
+/// Stops a stream. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    pub fn cancel(&self) { self.0.store(true, Ordering::Relaxed); }
+    pub fn is_cancelled(&self) -> bool { self.0.load(Ordering::Relaxed) }
+}
+
+/// Breadth-first walk below one root, one directory per step.
+pub struct Scan {
+    level: VecDeque<PathBuf>,
+    next: Vec<PathBuf>,
+    level_no: usize,
+    depth: usize,
+    visited: HashSet<FileId>,
+}
+
+impl Scan {
+    pub fn new(root: PathBuf, depth: usize) -> Self {
+        let visited = HashSet::from([FileId::of(&root)]);
+        Self { level: VecDeque::from([root]), next: Vec::new(), level_no: 1, depth, visited }
+    }
+
+    /// Matches from the next directory, or `None` once the walk is complete.
+    /// Names matching an `ignore` glob are neither reported nor descended into.
+    /// Each physical directory is entered once, so link cycles end the walk.
+    pub fn step(&mut self, engine: &SearchEngine, opts: &SearchOptions, ignore: &[SearchEngine]) -> Option<Vec<PathBuf>> {
+        if self.level_no > self.depth { return None; }
+        let Some(dir) = self.level.pop_front() else {
+            if self.next.is_empty() { return None; }
+            self.level = std::mem::take(&mut self.next).into();
+            self.level_no += 1;
+            return self.step(engine, opts, ignore);
+        };
+
+        let mut found = Vec::new();
+        // A running `ncd daemon` already knows the entries of indexed directories.
+        let children = daemon::children(&dir).unwrap_or_else(|| daemon::read_children(&dir));
+        for child in children {
+            if opts.links == LinkPolicy::Skip && child.link { continue; }
+            if ignore.iter().any(|g| g.matches_name(&child.name, false)) { continue; }
+            let path = dir.join(&child.name);
+            // Supports both 'exact match' and 'starts with' for fast typing.
+            if engine.matches_name(&child.name, opts.dir_match == DirMatch::Fuzzy) {
+                found.push(path.clone());
+            }
+            if self.level_no < self.depth && self.visited.insert(FileId::of(&path)) { self.next.push(path); }
+        }
+        Some(found)
+    }
+}
+
+/// Where a stream is within the current root.
+enum Stage { Direct, CwdGlob, Target, Origin, Done }
+
+/// Per-root state.
+struct Root {
+    path: PathBuf,
+    mode: CdMode,
+    depth: usize,
+    ignore: Vec<SearchEngine>,
+    stage: Stage,
+    scan: Option<Scan>,
+    hits: usize,
+    /// The CWD glob scan ran, so the origin scan would only repeat it.
+    globbed: bool,
+}
+
+impl Root {
+    fn new(settings: RootOptions, opts: &SearchOptions) -> Self {
+        // Per-root settings; a root without its own mode follows --cd / NCD_MODE.
+        Self {
+            mode: settings.mode.unwrap_or(opts.mode),
+            depth: settings.depth.unwrap_or(1).max(opts.min_depth),
+            ignore: settings.ignore.iter().map(|g| SearchEngine::new(g, false)).collect(),
+            path: settings.path,
+            stage: Stage::Direct,
+            scan: None,
+            hits: 0,
+            globbed: false,
+        }
+    }
+
+    /// Scans one more directory below the root. Returns false once the scan is finished.
+    fn scan_step(&mut self, engine: &SearchEngine, opts: &SearchOptions, out: &mut Output, phase: Phase) -> bool {
+        let scan = self.scan.get_or_insert_with(|| Scan::new(self.path.clone(), self.depth));
+        match scan.step(engine, opts, &self.ignore) {
+            Some(found) => {
+                for path in found {
+                    if out.push(path, phase) { self.hits += 1; }
+                }
+                true
+            }
+            None => { self.scan = None; false }
+        }
+    }
+}
+
+/// Deduplication and the queue of events not yet handed out.
+struct Output {
+    links: LinkPolicy,
+    /// `--has`: only directories with an entry matching this glob are matches.
+    has: Option<SearchEngine>,
+    /// Index of the root being searched.
+    root: usize,
+    seen: HashSet<FileId>,
+    pending: VecDeque<Event>,
+}
+
+impl Output {
+    /// Queues a match unless this directory was already reported. Returns true if queued.
+    fn push(&mut self, path: PathBuf, phase: Phase) -> bool {
+        if !self.seen.insert(self.links.dedupe_key(&path)) { return false; }
+        if self.has.as_ref().is_some_and(|glob| !has_entry(&path, glob)) { return false; }
+        let path = self.links.present(path);
+        self.pending.push_back(Event::Match(Candidate { path, phase }));
+        true
+    }
+}
+
+/// Whether `dir` has an entry (file or directory) whose name matches `glob`.
+fn has_entry(dir: &Path, glob: &SearchEngine) -> bool {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().any(|e| glob.matches_name(&e.file_name(), false)))
+        .unwrap_or(false)
+}
+
+/// Lazy search over all roots. See the module docs.
+pub struct Stream {
+    opts: SearchOptions,
+    engine: SearchEngine,
+    exact: bool,
+    roots: VecDeque<RootOptions>,
+    taken: usize,
+    current: Option<Root>,
+    sourced: bool,
+    out: Output,
+    cancel: Cancel,
+}
+
+impl Stream {
+    /// A stream for a directory name or glob.
+    pub fn new(name: impl AsRef<OsStr>, opts: &SearchOptions) -> Self {
+        Self::segment(&Segment::name(name.as_ref()), opts)
+    }
+
+    /// A stream for one parsed query segment, which may be a `re:` pattern.
+    pub fn segment(segment: &Segment, opts: &SearchOptions) -> Self {
+        let exact = is_case_sensitive(&segment.spelling(), opts);
+        Self {
+            opts: opts.clone(),
+            engine: SearchEngine::segment(segment, exact).ignoring_accents(opts.ignore_accents),
+            exact,
+            roots: search_roots(opts).into(),
+            taken: 0,
+            current: None,
+            sourced: false,
+            out: Output {
+                links: opts.links,
+                has: opts.has.as_ref().map(|g| SearchEngine::new(g, false)),
+                root: 0,
+                seen: HashSet::new(),
+                pending: VecDeque::new(),
+            },
+            cancel: Cancel::default(),
+        }
+    }
+
+    /// Handle that stops this stream; safe to use from other threads.
+    pub fn cancel_handle(&self) -> Cancel { self.cancel.clone() }
+
+    /// Whether the query matches case-sensitively (`--exact` or smart-case).
+    pub fn is_exact(&self) -> bool { self.exact }
+
+    pub fn is_wildcard(&self) -> bool { self.engine.is_wildcard }
+
+    /// Runs the next unit of work, queueing any events it produces.
+    /// Returns false when every root has been searched.
+    fn advance(&mut self) -> bool {
+        let Some(root) = self.current.as_mut() else {
+            let Some(settings) = self.roots.pop_front() else { return self.search_sources() };
+            self.out.root = self.taken;
+            self.taken += 1;
+            if settings.path.is_dir() { self.current = Some(Root::new(settings, &self.opts)); }
+            return true;
+        };
+        let first = self.out.root == 0;
+
+        match root.stage {
+            // PHASE A: DIRECT CHILD HIT (Absolute/Relative paths)
+            Stage::Direct => {
+                root.stage = Stage::CwdGlob;
+                let name = &self.engine.query;
+                let ignored = root.ignore.iter().any(|g| g.matches_name(name, false));
+                if !self.engine.is_wildcard && !name.is_empty() && !ignored {
+                    let hit = self.engine.check_direct(&root.path)
+                        .filter(|p| self.opts.links != LinkPolicy::Skip || !links::is_link(p));
+                    if let Some(path) = hit {
+                        if self.out.push(path, Phase::Direct) { root.hits += 1; }
+                    }
+                }
+            }
+            // Phase B: for CWD, wildcard matching, any mode.
+            Stage::CwdGlob => {
+                if self.engine.is_wildcard && first {
+                    if root.scan_step(&self.engine, &self.opts, &mut self.out, Phase::CwdGlob) { return true; }
+                    root.globbed = true;
+                }
+                root.stage = Stage::Target;
+            }
+            // PHASE C: TARGET (The folder itself is the bookmark)
+            Stage::Target => {
+                root.stage = Stage::Origin;
+                let is_mock_search = self.opts.mock_path.is_some();
+                let target = (!first || is_mock_search) && root.mode != CdMode::Origin;
+                // --file: a search root is a candidate in any mode, and its contents still are.
+                let candidate = target || (self.opts.root_candidates && !is_mock_search);
+                if candidate && self.engine.matches_path(&root.path)
+                    && self.out.push(root.path.clone(), Phase::Target) && target {
+                    root.hits += 1;
+                }
+            }
+            // PHASE D: ORIGIN (Search inside the folder)
+            Stage::Origin => {
+                if root.mode != CdMode::Target && (first || root.hits == 0) && !root.globbed
+                    && root.scan_step(&self.engine, &self.opts, &mut self.out, Phase::Origin) {
+                    return true;
+                }
+                root.stage = Stage::Done;
+            }
+            Stage::Done => {
+                let path = std::mem::take(&mut root.path);
+                self.current = None;
+                self.out.pending.push_back(Event::RootDone(path));
+            }
+        }
+        true
+    }
+}
+
+impl Stream {
+    /// Matches from `--sources`, once every root is done. A path-locked search
+    /// (a later query segment) stays inside its directory and skips them.
+    fn search_sources(&mut self) -> bool {
+        if self.sourced || self.opts.sources.is_empty() || self.opts.mock_path.is_some() { return false; }
+        self.sourced = true;
+        self.out.root = self.taken;
+        let fuzzy = self.opts.dir_match == DirMatch::Fuzzy;
+        for path in sources::candidates(&self.opts.sources) {
+            let Some(name) = path.file_name() else { continue };
+            if !self.engine.matches_name(name, fuzzy) || !path.is_dir() { continue; }
+            if self.opts.links == LinkPolicy::Skip && links::is_link(&path) { continue; }
+            self.out.push(path, Phase::Source);
+        }
+        self.out.pending.push_back(Event::RootDone(PathBuf::new()));
+        true
+    }
+}
+
+impl Iterator for Stream {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        loop {
+            if self.cancel.is_cancelled() { return None; }
+            if let Some(event) = self.out.pending.pop_front() { return Some(event); }
+            if !self.advance() { return None; }
+        }
+    }
+}
+
+/// Runs a stream on a worker thread. The worker stops when cancelled or when
+/// the receiver is dropped.
+pub fn spawn(name: impl AsRef<OsStr>, opts: &SearchOptions) -> (mpsc::Receiver<Event>, Cancel) {
+    let stream = Stream::new(name, opts);
+    let cancel = stream.cancel_handle();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for event in stream {
+            if tx.send(event).is_err() { break; }
+        }
+    });
+    (rx, cancel)
+}
@@ -0,0 +1,160 @@
+// src/transfer.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! `ncd import` and `ncd export`: moving history in and out of the store.
+//!
+//! Imports merge into ncd's store (see `store`): a directory that is already
+//! there has the ranks added and keeps the later visit time. Exports write the
+//! store to stdout; `--format=csv` output can be imported again with
+//! `--from=csv`, which is how two machines are kept in sync.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use lexopt::{Arg, Parser};
+use crate::listing::json_string;
+use crate::sources::{parse_autojump, parse_z, parse_zoxide};
+use crate::store::{self, Entry};
+use crate::NcdError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Z,
+    Fasd,
+    Autojump,
+    Zoxide,
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Self, NcdError> {
+        match s {
+            "z" => Ok(Self::Z),
+            "fasd" => Ok(Self::Fasd),
+            "autojump" => Ok(Self::Autojump),
+            "zoxide" => Ok(Self::Zoxide),
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err(NcdError::ArgError(format!("Invalid format: {}", s))),
+        }
+    }
+
+    /// Entries in a file of this format. JSON is written, never read.
+    pub fn read(self, bytes: &[u8]) -> Result<Vec<Entry>, NcdError> {
+        let text = || String::from_utf8_lossy(bytes);
+        match self {
+            // fasd kept z's file format.
+            Self::Z | Self::Fasd => Ok(parse_z(&text())),
+            Self::Autojump => Ok(parse_autojump(&text())),
+            Self::Zoxide => Ok(parse_zoxide(bytes)),
+            Self::Csv => parse_csv(&text()),
+            Self::Json => Err(NcdError::ArgError("JSON can be exported, not imported; use csv".into())),
+        }
+    }
+}
+
+/// `ncd import --from=<FORMAT> <FILE>`.
+pub fn import(args: impl IntoIterator<Item = OsString>) -> Result<(), NcdError> {
+    let mut parser = Parser::from_args(args);
+    let (mut from, mut file) = (None, None);
+    while let Some(arg) = parser.next().map_err(|e| NcdError::ArgError(e.to_string()))? {
+        match arg {
+            Arg::Long("from") => from = Some(Format::parse(&crate::text_value(&mut parser)?)?),
+            Arg::Value(v) if file.is_none() => file = Some(PathBuf::from(v)),
+            _ => return Err(NcdError::ArgError(format!("Unexpected import argument: {}", arg.unexpected()))),
+        }
+    }
+    let from = from.ok_or_else(|| NcdError::ArgError("import needs --from=z|autojump|zoxide|fasd|csv".into()))?;
+    let file = file.ok_or_else(|| NcdError::ArgError("import needs a file".into()))?;
+
+    let incoming = from.read(&std::fs::read(&file).map_err(NcdError::Io)?)?;
+    let total = incoming.len();
+    let mut entries = store::load();
+    let added = store::merge(&mut entries, incoming);
+    store::save(&entries).map_err(NcdError::Io)?;
+    println!("Imported {} directories ({} new) from {}", total, added, file.display());
+    Ok(())
+}
+
+/// `ncd export [--format=json|csv]`.
+pub fn export(args: impl IntoIterator<Item = OsString>) -> Result<(), NcdError> {
+    let mut parser = Parser::from_args(args);
+    let mut format = Format::Json;
+    while let Some(arg) = parser.next().map_err(|e| NcdError::ArgError(e.to_string()))? {
+        match arg {
+            Arg::Long("format") => format = Format::parse(&crate::text_value(&mut parser)?)?,
+            _ => return Err(NcdError::ArgError(format!("Unexpected export argument: {}", arg.unexpected()))),
+        }
+    }
+    let entries = store::load();
+    match format {
+        Format::Json => print!("{}", render_json(&entries)),
+        Format::Csv => print!("{}", render_csv(&entries)),
+        _ => return Err(NcdError::ArgError("export formats are json and csv".into())),
+    }
+    Ok(())
+}
+
+/// `[{"path": .., "rank": .., "last": ..}, ..]`, one entry per line.
+pub fn render_json(entries: &[Entry]) -> String {
+    let rows: Vec<String> = entries.iter().map(|e| {
+        format!("  {{\"path\":{},\"rank\":{},\"last\":{}}}", json_string(&e.path.to_string_lossy()), e.rank, e.last)
+    }).collect();
+    if rows.is_empty() { "[]\n".into() } else { format!("[\n{}\n]\n", rows.join(",\n")) }
+}
+
+/// RFC 4180: a `path,rank,last` header, paths quoted when they need it.
+pub fn render_csv(entries: &[Entry]) -> String {
+    let mut out = String::from("path,rank,last\n");
+    for e in entries {
+        let path = e.path.to_string_lossy();
+        if path.contains([',', '"', '\n', '\r']) {
+            out.push_str(&format!("\"{}\"", path.replace('"', "\"\"")));
+        } else {
+            out.push_str(&path);
+        }
+        out.push_str(&format!(",{},{}\n", e.rank, e.last));
+    }
+    out
+}
+
+/// Reads `render_csv` output. The header is required, so a wrong file is an error.
+pub fn parse_csv(text: &str) -> Result<Vec<Entry>, NcdError> {
+    let bad = |n: usize| NcdError::ArgError(format!("CSV line {}: expected path,rank,last", n));
+    let mut rows = csv_rows(text).into_iter();
+    if rows.next().as_deref() != Some(&["path".to_string(), "rank".to_string(), "last".to_string()][..]) {
+        return Err(bad(1));
+    }
+    rows.enumerate().map(|(i, row)| {
+        let [path, rank, last] = <[String; 3]>::try_from(row).map_err(|_| bad(i + 2))?;
+        Ok(Entry {
+            path: PathBuf::from(path),
+            rank: rank.trim().parse().map_err(|_| bad(i + 2))?,
+            last: last.trim().parse().map_err(|_| bad(i + 2))?,
+        })
+    }).collect()
+}
+
+/// Splits CSV text into rows of fields. Quoted fields may hold commas,
+/// doubled quotes and line breaks. Blank lines are skipped.
+fn csv_rows(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let (mut row, mut field) = (Vec::new(), String::new());
+    let (mut quoted, mut chars) = (false, text.chars().peekable());
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => { field.push('"'); chars.next(); }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.is_empty()) { rows.push(std::mem::take(&mut row)); } else { row.clear(); }
+            }
+            c => field.push(c),
+        }
+    }
+    row.push(field);
+    if row.iter().any(|f| !f.is_empty()) { rows.push(row); }
+    rows
+}
@@ -25,6 +25,7 @@ pub fn get_opts(mode: CdMode, exact: bool, mock: Option<OsString>) -> SearchOpti
         dir_match: DirMatch::default(),
         list: false, // Default to false for unit tests
         mock_path: mock,
+        ..Default::default()
     }
 }
 
@@ -35,6 +36,7 @@ pub fn get_opts_fuzzy(mode: CdMode, exact: bool, mock: Option<OsString>) -> Sear
         dir_match: DirMatch::Fuzzy,
         list: false, // Default to false for unit tests
         mock_path: mock,
+        ..Default::default()
     }
 }
 
@@ -56,7 +58,7 @@ pub fn get_test_root() -> PathBuf {
 }
 
 pub fn test_opts() -> SearchOptions {
-    SearchOptions { mode: CdMode::Origin, exact: true, list: false, mock_path: None, dir_match: DirMatch::default(), }
+    SearchOptions { mode: CdMode::Origin, exact: true, list: false, mock_path: None, dir_match: DirMatch::default(), ..Default::default() }
 }
 
 pub fn setup_test_env() -> (tempfile::TempDir, PathBuf) {
@@ -281,7 +283,7 @@ mod local_battery_1 {
         std::fs::create_dir_all(&cwd_mock).unwrap();
 
         // handle_ellipsis now only takes (segment, base) and returns Vec<PathBuf>
-        let matches = handle_ellipsis("..", cwd_mock);
+        let matches = handle_ellipsis(1, cwd_mock);
 
         assert!(!matches.is_empty(), "Matches should not be empty");
         let found = matches[0].canonicalize().unwrap();
@@ -319,7 +321,7 @@ mod local_battery_1 {
         std::fs::create_dir_all(&cwd_mock).unwrap();
 
         // "..." pops 2: CurrentDir -> DepthLayer -> root
-        let matches = handle_ellipsis("...", cwd_mock);
+        let matches = handle_ellipsis(2, cwd_mock);
 
         assert!(!matches.is_empty(), "Ellipsis should return the jumped path");
         let found_path = matches[0].canonicalize().unwrap();
@@ -397,6 +399,7 @@ mod local_battery_1 {
             list: false,
             dir_match: Default::default(),
             mock_path: Some(DEFAULT_TEST_ROOT.into()),
+            ..Default::default()
         };
 
         let results = search_cdpath("pro*", &opts);
@@ -497,7 +500,7 @@ mod local_battery_2 {
             ("Windows/Sys*", "Windows\\System32"), ("Proj*/ncd/src", "Projects\\ncd\\src")
         ];
         for (query, expected) in cases {
-            let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into_os_string()) };
+            let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into_os_string()), ..Default::default() };
             let res = evaluate_jump(query, &opts);
             assert!(res.iter().any(|p| p.to_string_lossy().contains(expected)), "Failed: {} -> {}", query, expected);
         }
@@ -507,7 +510,7 @@ mod local_battery_2 {
     fn test_path_resolutions() {
         let (_tmp, root) = setup_test_env();
         let root_str = root.to_str().unwrap();
-        let opts = |m: Option<String>| SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: m.map(|s| s.into()) };
+        let opts = |m: Option<String>| SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: m.map(|s| s.into()), ..Default::default() };
 
         // --- 1-3: AUTHORITY ---
         let res1 = evaluate_jump("Pr*", &opts(Some(root_str.into())));
@@ -529,7 +532,7 @@ mod local_battery_2 {
 
         // --- 8-9: ELLIPSIS ---
         let base = root.join("Projects").join("ncd");
-        let res8 = handle_ellipsis("...", base.clone()); // No .expect()
+        let res8 = handle_ellipsis(2, base.clone()); // No .expect()
 
         assert!(!res8.is_empty(), "Step 8 Failed: No results returned");
         let found8 = res8[0].canonicalize().unwrap();
@@ -602,7 +605,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_cases() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match:DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match:DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
 
         // 1. Interspersed dots: "Projects/./ncd" -> should resolve as "Projects/ncd"
         let res1 = evaluate_jump("Projects/./ncd", &opts);
@@ -648,7 +651,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_1() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 1. Interspersed dots: "Projects/./ncd" -> should resolve as "Projects/ncd"
         let res1 = evaluate_jump("Projects/./ncd", &opts);
         assert!(!res1.is_empty(), "Failed interspersed dot");
@@ -656,7 +659,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_2() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 2. Interspersed parent: "Projects/ncd/../Drivers" -> "Drivers"
         let res2 = evaluate_jump("Projects/ncd/../../Drivers", &opts);
         assert!(res2[0].ends_with("Drivers"), "Failed interspersed parent");
@@ -664,7 +667,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_3() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 3. Single character wildcard (?): "Pr?jects" -> "Projects"
         let res3 = evaluate_jump("Pr?jects", &opts);
         assert!(!res3.is_empty(), "Failed single-char wildcard '?'");
@@ -672,7 +675,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_4() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 4. Mixed wildcards: "P*j?cts" -> "Projects"
         let res4 = evaluate_jump("P*j?cts", &opts);
         assert!(!res4.is_empty(), "Failed mixed wildcards");
@@ -680,7 +683,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_5() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 5. Multiple ??: "syst??32" -> "System32"
         let res5 = evaluate_jump("Windows/syst??32", &opts);
         assert!(!res5.is_empty(), "Failed double '??'");
@@ -688,7 +691,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_6() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 6. Trailing slashes: "Projects/ncd/" -> Should not error
         let res6 = evaluate_jump("Projects/ncd/", &opts);
         assert!(!res6.is_empty(), "Failed trailing slash");
@@ -696,7 +699,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_7() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 7. Double slashes: "Projects//ncd" -> Should treat as single
         let res7 = evaluate_jump("Projects//ncd", &opts);
         assert!(!res7.is_empty(), "Failed double slash");
@@ -704,7 +707,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_8() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 8. The "Nop" jump: " . / . / . " -> current directory
         let res8 = evaluate_jump(" . / . / . ", &opts);
         assert_eq!(res8[0].canonicalize().unwrap(), env::current_dir().unwrap().canonicalize().unwrap());
@@ -712,7 +715,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_9a() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 9. Deep wildcards: "*/Guest/*top" -> "Users/Guest/Desktop"
         let res9 = evaluate_jump("*/Guest/*top", &opts);
         assert!(!res9.is_empty(), "Failed deep wildcard walk");
@@ -720,7 +723,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_9b() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Origin, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Origin, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 9. Deep wildcards: "*/Guest/*top" -> "Users/Guest/Desktop"
         let res9 = evaluate_jump("*/Guest/*top", &opts);
         assert!(!res9.is_empty(), "Failed deep wildcard walk");
@@ -728,7 +731,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_9c() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Target, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Target, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 9. Deep wildcards: "*/Guest/*top" -> "Users/Guest/Desktop"
         let res9 = evaluate_jump("*/Guest/*top", &opts);
         assert!(!res9.is_empty(), "Failed deep wildcard walk");
@@ -738,7 +741,7 @@ mod local_battery_2 {
     #[test]
     fn test_complex_edge_case_10() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match:DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match:DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // 10. Empty Query: "" -> should probably return current or empty
         let res10 = evaluate_jump("", &opts);
         assert!(res10.is_empty());
@@ -898,7 +901,7 @@ mod local_battery_2 {
     #[test]
     fn test_edge_interspersed_parents() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match:DirMatch::default(),  mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match:DirMatch::default(),  mock_path: Some(root.clone().into()), ..Default::default() };
         // Case: Projects/ncd/../Drivers -> should resolve to root/Drivers
         let res = evaluate_jump("Projects/ncd/../../Drivers", &opts);
         assert!(!res.is_empty(), "Failed interspersed parent jump");
@@ -908,7 +911,7 @@ mod local_battery_2 {
     #[test]
     fn test_edge_mixed_wildcards() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // Case: Pr*j?cts -> Projects
         let res = evaluate_jump("Pr*j?cts", &opts);
         assert!(!res.is_empty(), "Failed mixed * and ? wildcards");
@@ -918,7 +921,7 @@ mod local_battery_2 {
     #[test]
     fn test_edge_dot_navigation() {
         let (_tmp, root) = setup_test_env();
-        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()) };
+        let opts = SearchOptions { mode: CdMode::Hybrid, exact: false, list: false, dir_match: DirMatch::default(), mock_path: Some(root.clone().into()), ..Default::default() };
         // Case: Projects/./ncd -> Projects/ncd
         let res = evaluate_jump("Projects/./ncd", &opts);
         assert!(!res.is_empty(), "Failed interspersed dot navigation");
@@ -0,0 +1,229 @@
+// src/watch.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! Directory change notifications for `ncd daemon`.
+//!
+//! Linux uses inotify (one watch per indexed directory), Windows uses
+//! `ReadDirectoryChangesW` (one recursive watch per root, on its own thread).
+//! Elsewhere the watcher asks for a full rescan every few seconds. Both APIs
+//! are called through small hand-written bindings, like `identity.rs`.
+
+use std::path::{Path, PathBuf};
+
+/// What the index has to re-read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// The entries of this directory changed.
+    Dir(PathBuf),
+    /// Events were lost (queue overflow); rebuild everything.
+    Rescan,
+}
+
+#[cfg(target_os = "linux")]
+pub use self::inotify::Watcher;
+#[cfg(windows)]
+pub use self::windows::Watcher;
+#[cfg(not(any(target_os = "linux", windows)))]
+pub use self::poll::Watcher;
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::collections::HashMap;
+    use std::ffi::{c_char, c_int, CString};
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::fd::FromRawFd;
+    use std::os::unix::ffi::OsStrExt;
+    use super::*;
+
+    extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(fd: c_int, path: *const c_char, mask: u32) -> c_int;
+    }
+    const IN_CLOEXEC: c_int = 0o2000000;
+    const IN_MOVED_FROM: u32 = 0x40;
+    const IN_MOVED_TO: u32 = 0x80;
+    const IN_CREATE: u32 = 0x100;
+    const IN_DELETE: u32 = 0x200;
+    const IN_Q_OVERFLOW: u32 = 0x4000;
+    const IN_IGNORED: u32 = 0x8000;
+    const IN_ONLYDIR: u32 = 0x0100_0000;
+    /// Header of `struct inotify_event`: wd, mask, cookie, len.
+    const HEADER: usize = 16;
+
+    pub struct Watcher {
+        file: File,
+        fd: c_int,
+        dirs: HashMap<c_int, PathBuf>,
+    }
+
+    impl Watcher {
+        pub fn new() -> io::Result<Self> {
+            // SAFETY: plain syscall wrapper; a negative result is checked below.
+            let fd = unsafe { inotify_init1(IN_CLOEXEC) };
+            if fd < 0 { return Err(io::Error::last_os_error()); }
+            // SAFETY: `fd` is a fresh descriptor that nothing else owns.
+            let file = unsafe { File::from_raw_fd(fd) };
+            Ok(Self { file, fd, dirs: HashMap::new() })
+        }
+
+        /// Reports entries created, deleted or renamed in `dir` (not below it).
+        pub fn add(&mut self, dir: &Path) {
+            let Ok(path) = CString::new(dir.as_os_str().as_bytes()) else { return };
+            let mask = IN_CREATE | IN_DELETE | IN_MOVED_FROM | IN_MOVED_TO | IN_ONLYDIR;
+            // SAFETY: `path` is NUL-terminated and outlives the call.
+            let wd = unsafe { inotify_add_watch(self.fd, path.as_ptr(), mask) };
+            if wd >= 0 { self.dirs.insert(wd, dir.to_path_buf()); }
+        }
+
+        /// Blocks until something changes.
+        pub fn next(&mut self) -> io::Result<Vec<Change>> {
+            let mut buf = [0u8; 16 * 1024];
+            let n = self.file.read(&mut buf)?;
+            let word = |at: usize| u32::from_ne_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]]);
+            let mut changes = Vec::new();
+            let mut at = 0;
+            while at + HEADER <= n {
+                let (wd, mask, len) = (word(at) as c_int, word(at + 4), word(at + 12) as usize);
+                at += HEADER + len;
+                if mask & IN_Q_OVERFLOW != 0 { return Ok(vec![Change::Rescan]); }
+                // The kernel dropped the watch (directory deleted or unmounted).
+                if mask & IN_IGNORED != 0 { self.dirs.remove(&wd); continue; }
+                if let Some(dir) = self.dirs.get(&wd) {
+                    let change = Change::Dir(dir.clone());
+                    if !changes.contains(&change) { changes.push(change); }
+                }
+            }
+            Ok(changes)
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::{c_void, OsString};
+    use std::fs::OpenOptions;
+    use std::io;
+    use std::os::windows::ffi::OsStringExt;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use std::sync::mpsc;
+    use super::*;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn ReadDirectoryChangesW(
+            dir: *mut c_void, buf: *mut c_void, len: u32, subtree: i32, filter: u32,
+            returned: *mut u32, overlapped: *mut c_void, completion: *mut c_void,
+        ) -> i32;
+    }
+    const FILE_LIST_DIRECTORY: u32 = 0x1;
+    const FILE_SHARE_ALL: u32 = 0x7; // read | write | delete: never block the user's renames
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    const FILE_NOTIFY_CHANGE_FILE_NAME: u32 = 0x1;
+    const FILE_NOTIFY_CHANGE_DIR_NAME: u32 = 0x2;
+
+    pub struct Watcher {
+        tx: mpsc::Sender<Change>,
+        rx: mpsc::Receiver<Change>,
+        roots: Vec<PathBuf>,
+    }
+
+    impl Watcher {
+        pub fn new() -> io::Result<Self> {
+            let (tx, rx) = mpsc::channel();
+            Ok(Self { tx, rx, roots: Vec::new() })
+        }
+
+        /// Reports changes anywhere below `dir`. Directories already covered
+        /// by a watched ancestor need no watch of their own.
+        pub fn add(&mut self, dir: &Path) {
+            if self.roots.iter().any(|r| dir.starts_with(r)) { return; }
+            self.roots.push(dir.to_path_buf());
+            let (dir, tx) = (dir.to_path_buf(), self.tx.clone());
+            std::thread::spawn(move || watch_tree(&dir, &tx));
+        }
+
+        /// Blocks until something changes.
+        pub fn next(&mut self) -> io::Result<Vec<Change>> {
+            let first = self.rx.recv().map_err(|e| io::Error::other(e.to_string()))?;
+            let mut changes = vec![first];
+            while let Ok(c) = self.rx.try_recv() {
+                if !changes.contains(&c) { changes.push(c); }
+            }
+            Ok(changes)
+        }
+    }
+
+    fn watch_tree(dir: &Path, tx: &mpsc::Sender<Change>) {
+        let Ok(handle) = OpenOptions::new()
+            .access_mode(FILE_LIST_DIRECTORY)
+            .share_mode(FILE_SHARE_ALL)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(dir) else { return };
+        // FILE_NOTIFY_INFORMATION records must be DWORD aligned.
+        let mut buf = vec![0u32; 16 * 1024];
+        loop {
+            let mut returned = 0u32;
+            // SAFETY: the handle stays open for the call, `buf` is writable for
+            // the length given, and a null OVERLAPPED makes the call synchronous.
+            let ok = unsafe {
+                ReadDirectoryChangesW(
+                    handle.as_raw_handle().cast(), buf.as_mut_ptr().cast(), (buf.len() * 4) as u32, 1,
+                    FILE_NOTIFY_CHANGE_FILE_NAME | FILE_NOTIFY_CHANGE_DIR_NAME,
+                    &mut returned, std::ptr::null_mut(), std::ptr::null_mut(),
+                )
+            };
+            // The watched directory itself is gone.
+            if ok == 0 { return; }
+            let sent = if returned == 0 {
+                // The buffer overflowed and the events are lost.
+                tx.send(Change::Rescan)
+            } else {
+                parse(&buf, returned as usize).into_iter()
+                    .try_for_each(|name| tx.send(Change::Dir(dir.join(name).parent().unwrap_or(dir).to_path_buf())))
+            };
+            if sent.is_err() { return; }
+        }
+    }
+
+    /// Relative names from a chain of FILE_NOTIFY_INFORMATION records:
+    /// next offset, action, name length in bytes, then the UTF-16 name.
+    fn parse(buf: &[u32], len: usize) -> Vec<OsString> {
+        let bytes: Vec<u8> = buf.iter().flat_map(|w| w.to_ne_bytes()).take(len).collect();
+        let word = |at: usize| u32::from_ne_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as usize;
+        let mut names = Vec::new();
+        let mut at = 0;
+        while at + 12 <= bytes.len() {
+            let (next, name_len) = (word(at), word(at + 8));
+            let end = (at + 12 + name_len).min(bytes.len());
+            let wide: Vec<u16> = bytes[at + 12..end].chunks_exact(2)
+                .map(|c| u16::from_ne_bytes([c[0], c[1]])).collect();
+            names.push(OsString::from_wide(&wide));
+            if next == 0 { break; }
+            at += next;
+        }
+        names
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod poll {
+    use std::io;
+    use std::time::Duration;
+    use super::*;
+
+    /// No native binding here: rebuild the index periodically instead.
+    pub struct Watcher;
+
+    impl Watcher {
+        pub fn new() -> io::Result<Self> { Ok(Self) }
+
+        pub fn add(&mut self, _dir: &Path) {}
+
+        pub fn next(&mut self) -> io::Result<Vec<Change>> {
+            std::thread::sleep(Duration::from_secs(5));
+            Ok(vec![Change::Rescan])
+        }
+    }
+}
@@ -0,0 +1,188 @@
+// src/workspace.rs
+// License: PolyForm Noncommercial 1.0.0 (Personal & Research Use Only)
+//
+//! `ws:<member>`: a workspace member, found by its package name.
+//!
+//! The nearest directory above CWD that declares a workspace is its root;
+//! every kind of workspace declared there counts:
+//!
+//! | File                  | Members                                 | Named by                 |
+//! | --------------------- | --------------------------------------- | ------------------------ |
+//! | `Cargo.toml`          | `[workspace] members`                   | `[package] name`         |
+//! | `package.json`        | `workspaces` (or `workspaces.packages`) | `name` in `package.json` |
+//! | `pnpm-workspace.yaml` | `packages:` list                        | `name` in `package.json` |
+//! | `go.work`             | `use` directives                        | `module` in `go.mod`     |
+//!
+//! Member patterns may hold `*` in any component and `**` for any depth;
+//! `!pattern` entries exclude. A scoped or path-like name (`@acme/ui`,
+//! `example.com/tools/lint`) also answers to its last part (`ui`, `lint`).
+//! Like `cargo`, the files are read with a few text rules, not full parsers.
+
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+use crate::cargo::{self, quoted, Manifest};
+use crate::SearchEngine;
+
+/// How deep `**` reaches.
+const GLOB_DEPTH: usize = 4;
+
+/// A member of the workspace and the package name it goes by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+/// Directories of the members called `name` in the workspace around `cwd`.
+pub fn member_dirs(cwd: &Path, name: &str) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for m in members(cwd) {
+        let short = m.name.rsplit('/').next().unwrap_or(&m.name);
+        if (m.name == name || short == name) && !dirs.contains(&m.dir) { dirs.push(m.dir); }
+    }
+    dirs
+}
+
+/// Every member of the nearest enclosing workspace.
+pub fn members(cwd: &Path) -> Vec<Member> {
+    cwd.ancestors().map(members_at).find(|m| !m.is_empty()).unwrap_or_default()
+}
+
+/// The members declared by the workspace files in `root`.
+fn members_at(root: &Path) -> Vec<Member> {
+    let read = |file: &str| std::fs::read_to_string(root.join(file)).ok();
+    let mut out = Vec::new();
+    if let Some(text) = read("Cargo.toml") {
+        let patterns = Manifest::parse(&text).members;
+        out.extend(expand_all(root, &patterns).into_iter()
+            .filter_map(|dir| Some(Member { name: cargo::package_name(&dir.join("Cargo.toml"))?, dir })));
+    }
+    let mut node = read("package.json").map(|t| npm_workspaces(&t)).unwrap_or_default();
+    node.extend(read("pnpm-workspace.yaml").map(|t| pnpm_packages(&t)).unwrap_or_default());
+    out.extend(expand_all(root, &node).into_iter()
+        .filter_map(|dir| Some(Member { name: npm_name(&std::fs::read_to_string(dir.join("package.json")).ok()?)?, dir })));
+    if let Some(text) = read("go.work") {
+        out.extend(go_uses(&text).iter().map(|u| join_lexical(root, u))
+            .filter_map(|dir| Some(Member { name: go_module(&std::fs::read_to_string(dir.join("go.mod")).ok()?)?, dir })));
+    }
+    out
+}
+
+/// The directories `patterns` name below `root`, minus the `!` exclusions.
+fn expand_all(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
+    let (excluded, included): (Vec<&String>, Vec<&String>) = patterns.iter().partition(|p| p.starts_with('!'));
+    let gone: Vec<PathBuf> = excluded.iter().flat_map(|p| expand_member(root, &p[1..])).collect();
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for d in included.iter().flat_map(|p| expand_member(root, p)) {
+        if !gone.contains(&d) && !dirs.contains(&d) { dirs.push(d); }
+    }
+    dirs
+}
+
+/// `root/pattern`, where a component may be a glob (`crates/*`) or `**`.
+/// Only existing directories come back, except for a pattern without globs.
+pub fn expand_member(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let pattern = pattern.trim_end_matches('/');
+    if !pattern.contains(['*', '?']) { return vec![join_lexical(root, pattern)]; }
+    let mut dirs = vec![root.to_path_buf()];
+    for part in pattern.split('/').filter(|p| !p.is_empty() && *p != ".") {
+        dirs = match part {
+            "**" => dirs.iter().flat_map(|d| below(d, GLOB_DEPTH)).collect(),
+            _ if part.contains(['*', '?']) => {
+                let glob = SearchEngine::new(part, true);
+                dirs.iter().flat_map(|d| subdirs(d)).filter(|d| d.file_name().is_some_and(|n| glob.matches_name(n, false))).collect()
+            }
+            _ => dirs.iter().map(|d| join_lexical(d, part)).filter(|d| d.is_dir()).collect(),
+        };
+    }
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+/// `dir` and the directories below it, `depth` levels deep.
+fn below(dir: &Path, depth: usize) -> Vec<PathBuf> {
+    let mut out = vec![dir.to_path_buf()];
+    if depth > 0 { out.extend(subdirs(dir).iter().flat_map(|d| below(d, depth - 1))); }
+    out
+}
+
+/// Subdirectories, without hidden ones, `node_modules` and `target`.
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let skip = |n: &OsStr| n.as_encoded_bytes().starts_with(b".") || n == "node_modules" || n == "target";
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(dir).map(|entries| {
+        entries.flatten().filter(|e| !skip(&e.file_name())).map(|e| e.path()).filter(|p| p.is_dir()).collect()
+    }).unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+/// `dir/rel` with `..` applied, so `../x` prints as a plain path.
+pub fn join_lexical(dir: &Path, rel: &str) -> PathBuf {
+    let mut out = dir.to_path_buf();
+    for c in Path::new(rel).components() {
+        match c {
+            Component::ParentDir if out.file_name().is_some() => { out.pop(); }
+            Component::CurDir => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// `"workspaces": [..]` or `"workspaces": { "packages": [..] }` in a package.json.
+pub fn npm_workspaces(text: &str) -> Vec<String> {
+    let Some(at) = text.find("\"workspaces\"") else { return Vec::new() };
+    let rest = &text[at..];
+    let Some(open) = rest.find('[') else { return Vec::new() };
+    let list = &rest[open + 1..];
+    quoted(&list[..list.find(']').unwrap_or(list.len())])
+}
+
+/// The package's `"name"`: the first one in the file, where npm writes it.
+pub fn npm_name(text: &str) -> Option<String> {
+    let re = regex::Regex::new(r#""name"\s*:\s*"([^"]+)""#).ok()?;
+    Some(re.captures(text)?[1].to_string())
+}
+
+/// The `packages:` list of a pnpm-workspace.yaml.
+pub fn pnpm_packages(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut inside = false;
+    for line in text.lines() {
+        let line = line.split(" #").next().unwrap_or("");
+        if !line.starts_with([' ', '\t', '-']) && !line.trim().is_empty() {
+            inside = line.trim_end() == "packages:";
+            continue;
+        }
+        if let Some(item) = line.trim().strip_prefix('-').filter(|_| inside) {
+            out.push(item.trim().trim_matches(['"', '\'']).to_string());
+        }
+    }
+    out
+}
+
+/// The directories of a go.work's `use ./x` and `use ( .. )` directives.
+pub fn go_uses(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut block = false;
+    for line in text.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if block {
+            if line == ")" { block = false; } else if !line.is_empty() { out.push(line.trim_matches('"').to_string()); }
+            continue;
+        }
+        let Some(rest) = line.strip_prefix("use").filter(|r| r.starts_with([' ', '\t', '('])) else { continue };
+        match rest.trim() {
+            "(" => block = true,
+            dir if !dir.is_empty() => out.push(dir.trim_matches('"').to_string()),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// The `module` path of a go.mod.
+pub fn go_module(text: &str) -> Option<String> {
+    text.lines().find_map(|l| l.trim().strip_prefix("module ")).map(|m| m.trim().trim_matches('"').to_string())
+}
@@ -36,6 +36,10 @@ impl CwdGuard {
     }
 }
 #[cfg(test)]
+impl Default for CwdGuard {
+    fn default() -> Self { Self::new() }
+}
+#[cfg(test)]
 impl Drop for CwdGuard {
     fn drop(&mut self) {
         let _ = std::env::set_current_dir(&self.old_cwd);
@@ -437,7 +441,7 @@ mod remotes {
         // 1. Test deep path scrubbing (trailing slash)
         let path_str = format!("{}Projects{}ncd{}", &anchor, MAIN_SEPARATOR, MAIN_SEPARATOR);
         let cleaned = if path_str.len() > 3 {
-            path_str.trim_end_matches(|c| c == '\\' || c == '/').to_string()
+            path_str.trim_end_matches(['\\', '/']).to_string()
         } else {
             path_str.clone()
         };
@@ -447,7 +451,7 @@ mod remotes {
         // 2. Test Root Protection (Should NOT scrub the C:\)
         let root = &anchor;
         let cleaned_root = if root.len() > 3 {
-            root.trim_end_matches(|c| c == '\\' || c == '/').to_string()
+            root.trim_end_matches(['\\', '/']).to_string()
         } else {
             root.clone()
         };
@@ -621,4 +625,1473 @@ mod final_series {
             cargo_bin_cmd!("ncd").arg("~").assert().success();
         }
     }
-}
\ No newline at end of file
+}
+
+mod ranking {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::predicate;
+
+    #[test]
+    fn test_first_picks_exact_over_prefix() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("web")).unwrap();
+        fs::create_dir_all(tmp.path().join("webapp")).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["--glob", "--first", "WEB"])
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("web\n"));
+    }
+    #[test]
+    fn test_best_refuses_close_call() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("match_one")).unwrap();
+        fs::create_dir_all(tmp.path().join("match_two")).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["--best", "match*"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Ambiguous match"));
+    }
+    #[test]
+    fn test_best_accepts_clear_winner() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cdpath = tmp.path().join("bookmarks");
+        fs::create_dir_all(tmp.path().join("work/build")).unwrap();
+        fs::create_dir_all(cdpath.join("build")).unwrap();
+
+        // Same match kind and depth; CWD outranks the CDPATH entry by one root step.
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("work"))
+            .env("CDPATH", &cdpath)
+            .args(["--best", "bui*"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("work"));
+    }
+    #[test]
+    fn test_frecent_prefers_visited_directory() {
+        let tmp = tempfile::tempdir().unwrap();
+        for d in ["proj_a", "proj_b"] { fs::create_dir_all(tmp.path().join(d)).unwrap(); }
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+        let store = tmp.path().join("history.tsv");
+        fs::write(&store, format!("12\t{}\t{}\n", now, tmp.path().join("proj_b").display())).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .env("NCD_STORE", &store)
+            .args(["--first", "proj*"])
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("proj_a\n"));
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .env("NCD_STORE", &store)
+            .args(["--first", "--frecent", "proj*"])
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("proj_b\n"));
+    }
+}
+
+mod listing {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::boolean::PredicateBooleanExt;
+    use predicates::prelude::predicate;
+
+    #[test]
+    fn test_list_sort_limit_and_contains() {
+        let tmp = tempfile::tempdir().unwrap();
+        for d in ["svc_b", "svc_a", "svc_c"] { fs::create_dir_all(tmp.path().join(d)).unwrap(); }
+        fs::write(tmp.path().join("svc_c/Cargo.toml"), "").unwrap();
+
+        let out = cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["--list", "--sort=name", "--limit=2", "svc_*"])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&out.stdout);
+        let names: Vec<_> = stdout.lines().map(|l| l.rsplit(['/', '\\']).next().unwrap()).collect();
+        assert_eq!(names, ["svc_a", "svc_b"]);
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["--list", "--contains", "Cargo.toml", "svc_*"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("svc_c").and(predicate::str::contains("svc_a").not()));
+    }
+    #[test]
+    fn test_list_rejects_unknown_sort_key() {
+        cargo_bin_cmd!("ncd")
+            .args(["--list", "--sort=size", "x*"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid sort key"));
+    }
+}
+
+mod path_style {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::boolean::PredicateBooleanExt;
+    use predicates::prelude::predicate;
+
+    #[test]
+    fn test_windows_style_output() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("styled")).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["--path-style=windows", "styled"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("\\styled").and(predicate::str::contains("/").not()));
+    }
+    #[test]
+    fn test_env_path_style_rejects_unknown() {
+        cargo_bin_cmd!("ncd")
+            .env("NCD_PATH_STYLE", "dos")
+            .arg(".")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid path style"));
+    }
+}
+
+mod long_paths {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+
+    #[test]
+    fn test_resolves_and_prints_paths_over_max_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut deep = tmp.path().to_path_buf();
+        for i in 0..12 { deep.push(format!("segment_{:02}_{}", i, "x".repeat(20))); }
+        fs::create_dir_all(deep.join("leaf_target")).unwrap();
+        assert!(deep.to_string_lossy().len() > 260);
+
+        let out = cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("CDPATH", &deep)
+            .arg("leaf_target")
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+        let printed = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        assert!(std::path::Path::new(&printed).is_dir(), "Printed path is not usable: {}", printed);
+        assert!(printed.ends_with("leaf_target"));
+    }
+}
+
+#[cfg(unix)]
+mod non_utf8 {
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::os::unix::ffi::OsStrExt;
+    use assert_cmd::cargo_bin_cmd;
+
+    #[test]
+    fn test_non_utf8_query_prints_raw_bytes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let name = OsStr::from_bytes(b"caf\xE9");
+        fs::create_dir_all(tmp.path().join(name)).unwrap();
+
+        let out = cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .arg(name)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+        assert!(out.stdout.ends_with(b"/caf\xE9\n"), "Bytes were not passed through: {:?}", out.stdout);
+    }
+    #[test]
+    fn test_non_utf8_option_value_is_rejected() {
+        cargo_bin_cmd!("ncd")
+            .arg("--sort")
+            .arg(OsStr::from_bytes(b"n\xE9me"))
+            .arg(".")
+            .assert()
+            .failure();
+    }
+}
+
+mod unicode_matching {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_full_case_folding_from_cli() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("Stra\u{df}e")).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .arg("STRASSE")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Stra\u{df}e"));
+    }
+    #[test]
+    fn test_ignore_accents_flag() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("R\u{e9}sum\u{e9}s")).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .arg("resumes")
+            .assert()
+            .failure();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["--ignore-accents", "resumes"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("R\u{e9}sum\u{e9}s"));
+    }
+}
+
+mod smart_case {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    fn sandbox() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("Build")).unwrap();
+        tmp
+    }
+
+    #[test]
+    fn test_flag_and_env() {
+        let tmp = sandbox();
+        for (args, env) in [(vec!["--smart-case", "BUILD"], None), (vec!["BUILD"], Some("1"))] {
+            let mut cmd = cargo_bin_cmd!("ncd");
+            cmd.current_dir(tmp.path()).env_remove("CDPATH").env("NCD_CONFIG", tmp.path().join("none.conf"));
+            if let Some(v) = env { cmd.env("NCD_SMART_CASE", v); }
+            cmd.args(args).assert().failure();
+        }
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .env("NCD_SMART_CASE", "1")
+            .arg("build")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Build"));
+    }
+    #[test]
+    fn test_config_file_and_override() {
+        let tmp = sandbox();
+        let conf = tmp.path().join("ncd.conf");
+        fs::write(&conf, "smart-case = yes\n").unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .env_remove("NCD_SMART_CASE")
+            .env("NCD_CONFIG", &conf)
+            .arg("BUILD")
+            .assert()
+            .failure();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .env_remove("NCD_SMART_CASE")
+            .env("NCD_CONFIG", &conf)
+            .args(["--no-smart-case", "BUILD"])
+            .assert()
+            .success();
+
+        fs::write(&conf, "smart-case = perhaps\n").unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("NCD_SMART_CASE")
+            .env("NCD_CONFIG", &conf)
+            .arg("BUILD")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Config error"));
+    }
+}
+
+mod root_options {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    /// `marks` is a bookmark root; `work` holds nested projects and a node_modules.
+    fn sandbox() -> (tempfile::TempDir, std::path::PathBuf, std::path::PathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let marks = tmp.path().join("marks");
+        let work = tmp.path().join("work");
+        fs::create_dir_all(marks.join("inner")).unwrap();
+        fs::create_dir_all(work.join("app/docs")).unwrap();
+        fs::create_dir_all(work.join("node_modules/lib")).unwrap();
+        fs::create_dir_all(tmp.path().join("cwd")).unwrap();
+        (tmp, marks, work)
+    }
+
+    #[test]
+    fn test_mode_per_root() {
+        let (tmp, marks, work) = sandbox();
+        let conf = tmp.path().join("ncd.conf");
+        fs::write(&conf, format!("[root {}]\nmode = target\n", marks.display())).unwrap();
+        let cdpath = std::env::join_paths([&marks, &work]).unwrap();
+
+        // Global Origin mode, but `marks` is a bookmark root: its own name matches.
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", &cdpath)
+            .env("NCD_CONFIG", &conf)
+            .env_remove("NCD_MODE")
+            .arg("marks")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("marks\n"));
+        // Without the annotation the same query finds nothing in Origin mode.
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", &cdpath)
+            .env("NCD_CONFIG", tmp.path().join("missing.conf"))
+            .env_remove("NCD_MODE")
+            .arg("marks")
+            .assert()
+            .failure();
+    }
+    #[test]
+    fn test_depth_and_ignore_per_root() {
+        let (tmp, _marks, work) = sandbox();
+        let conf = tmp.path().join("ncd.conf");
+        fs::write(&conf, format!("[root {}]\ndepth = 2\nignore = node_modules\n", work.display())).unwrap();
+
+        let run = |q: &str| {
+            let mut cmd = cargo_bin_cmd!("ncd");
+            cmd.current_dir(tmp.path().join("cwd")).env("CDPATH", &work).env("NCD_CONFIG", &conf).arg(q);
+            cmd.assert()
+        };
+        run("docs").success().stdout(predicate::str::contains("app"));
+        run("lib").failure();
+    }
+    #[test]
+    fn test_configured_root_outside_cdpath() {
+        let (tmp, _marks, work) = sandbox();
+        let conf = tmp.path().join("ncd.conf");
+        fs::write(&conf, format!("[root {}]\n", work.display())).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env_remove("CDPATH")
+            .env("NCD_CONFIG", &conf)
+            .arg("app")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("work"));
+    }
+}
+
+#[cfg(unix)]
+mod links {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+    use predicates::boolean::PredicateBooleanExt;
+
+    fn sandbox() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("store/real_app")).unwrap();
+        symlink(tmp.path().join("store/real_app"), tmp.path().join("app_link")).unwrap();
+        tmp
+    }
+
+    #[test]
+    fn test_links_policies_from_cli() {
+        let tmp = sandbox();
+        let run = |args: &[&str]| {
+            let mut cmd = cargo_bin_cmd!("ncd");
+            cmd.current_dir(tmp.path()).env_remove("CDPATH").args(args);
+            cmd.assert()
+        };
+        run(&["app_link"]).success().stdout(predicate::str::ends_with("app_link\n"));
+        run(&["--links=physical", "app_link"]).success().stdout(predicate::str::ends_with("real_app\n"));
+        run(&["--links=skip", "app_link"]).failure();
+        run(&["--links=sideways", "app_link"]).failure().stderr(predicate::str::contains("Invalid link policy"));
+    }
+    #[test]
+    fn test_list_and_json_show_link_status() {
+        let tmp = sandbox();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["-l", "app*"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("app_link -> ").and(predicate::str::contains("real_app")));
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["--json", "*"])
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with("[{\"path\":")
+                .and(predicate::str::contains("\"link\":true,\"target\":\""))
+                .and(predicate::str::contains("\"link\":false,\"target\":null")));
+    }
+}
+
+mod dedupe {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+
+    #[test]
+    fn test_root_listed_twice_reports_each_directory_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("root");
+        for d in ["b_app", "a_app", "c_app"] { fs::create_dir_all(root.join(d)).unwrap(); }
+        fs::create_dir_all(tmp.path().join("cwd")).unwrap();
+        // Same root, two spellings.
+        let cdpath = std::env::join_paths([root.clone(), root.join(".").join("..").join("root")]).unwrap();
+
+        let out = cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", &cdpath)
+            .args(["-l", "*_app"])
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        let names: Vec<String> = String::from_utf8_lossy(&out.stdout).lines()
+            .map(|l| std::path::Path::new(l).file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["a_app", "b_app", "c_app"], "One entry per directory, in name order");
+    }
+}
+
+mod streaming {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+
+    #[test]
+    fn test_list_limit_stops_after_n_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        for d in ["app_c", "app_a", "app_b"] { fs::create_dir_all(tmp.path().join(d)).unwrap(); }
+
+        let out = cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["-l", "--limit", "2", "app_*"])
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        let names: Vec<String> = String::from_utf8_lossy(&out.stdout).lines()
+            .map(|l| std::path::Path::new(l).file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["app_a", "app_b"]);
+    }
+
+    #[test]
+    fn test_streamed_list_reports_no_match() {
+        let tmp = tempfile::tempdir().unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["-l", "nothing_here"])
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("Could not resolve"));
+    }
+}
+
+#[cfg(unix)]
+mod daemon {
+    use std::fs;
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    fn status(socket: &Path) -> Option<usize> {
+        let out = cargo_bin_cmd!("ncd").env("NCD_SOCKET", socket).args(["daemon", "--status"]).output().unwrap();
+        let text = String::from_utf8_lossy(&out.stdout);
+        text.split(": ").nth(1)?.split(' ').next()?.parse().ok()
+    }
+
+    fn wait_for(socket: &Path, want: impl Fn(usize) -> bool) -> Option<usize> {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(10) {
+            if let Some(n) = status(socket).filter(|n| want(*n)) { return Some(n); }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        None
+    }
+
+    #[test]
+    fn test_daemon_tracks_new_directories() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("root");
+        for d in ["app_a", "app_b"] { fs::create_dir_all(root.join(d)).unwrap(); }
+        let socket = tmp.path().join("ncd.sock");
+
+        let mut daemon = std::process::Command::new(assert_cmd::cargo::cargo_bin!("ncd"))
+            .arg("daemon")
+            .current_dir(tmp.path())
+            .env("CDPATH", &root)
+            .env("NCD_SOCKET", &socket)
+            .env("NCD_CONFIG", tmp.path().join("none.conf"))
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+
+        assert_eq!(wait_for(&socket, |_| true), Some(2));
+        fs::create_dir(root.join("app_c")).unwrap();
+        assert_eq!(wait_for(&socket, |n| n == 3), Some(3), "The new folder reaches the index");
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("CDPATH", &root)
+            .env("NCD_SOCKET", &socket)
+            .args(["-l", "app_*"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("app_c"));
+
+        cargo_bin_cmd!("ncd").env("NCD_SOCKET", &socket).args(["daemon", "--stop"]).assert().success();
+        assert!(daemon.wait().unwrap().success());
+        assert!(!socket.exists(), "The socket is removed on stop");
+    }
+
+    #[test]
+    fn test_cli_without_daemon_scans_live() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("app_a")).unwrap();
+        let socket = tmp.path().join("absent.sock");
+
+        cargo_bin_cmd!("ncd")
+            .env("NCD_SOCKET", &socket)
+            .args(["daemon", "--status"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Daemon error: not running"));
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .env("NCD_SOCKET", &socket)
+            .arg("app_a")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("app_a"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_daemon_refuses_shared_socket_dir() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        let tmp = tempfile::tempdir().unwrap();
+        let uid = fs::metadata(tmp.path()).unwrap().uid();
+        let dir = tmp.path().join(format!("ncd-{}", uid));
+        fs::create_dir(&dir).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .env("TMPDIR", tmp.path())
+            .env_remove("XDG_RUNTIME_DIR")
+            .env_remove("NCD_SOCKET")
+            .arg("daemon")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("not a private directory"));
+    }
+}
+
+mod sources {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_z_database_answers_when_roots_do_not() {
+        let tmp = tempfile::tempdir().unwrap();
+        let far = tmp.path().join("elsewhere/deep/far_proj");
+        fs::create_dir_all(&far).unwrap();
+        fs::create_dir_all(tmp.path().join("cwd")).unwrap();
+        let z = tmp.path().join("z.txt");
+        fs::write(&z, format!("{}|5|1700000000\n{}|9|1700000000\n", far.display(), tmp.path().join("gone").display())).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env_remove("CDPATH")
+            .env("_Z_DATA", &z)
+            .args(["--sources=z", "far_proj"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("far_proj"));
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env_remove("CDPATH")
+            .env("_Z_DATA", &z)
+            .arg("far_proj")
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn test_live_match_outranks_source() {
+        let tmp = tempfile::tempdir().unwrap();
+        let far = tmp.path().join("elsewhere/app");
+        fs::create_dir_all(&far).unwrap();
+        fs::create_dir_all(tmp.path().join("cwd/app")).unwrap();
+        let z = tmp.path().join("z.txt");
+        fs::write(&z, format!("{}|5|1700000000\n", far.display())).unwrap();
+
+        let out = cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env_remove("CDPATH")
+            .env("_Z_DATA", &z)
+            .args(["--sources=z", "-l", "ap*"])
+            .output()
+            .unwrap();
+        let lines: Vec<String> = String::from_utf8_lossy(&out.stdout).lines().map(String::from).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("cwd"), "Live match first: {:?}", lines);
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env_remove("CDPATH")
+            .env("_Z_DATA", &z)
+            .args(["--sources=z", "ap*"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("cwd"));
+    }
+
+    #[test]
+    fn test_recent_source_remembers_jumps() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("work/proj_x")).unwrap();
+        fs::create_dir_all(tmp.path().join("other")).unwrap();
+        let store = tmp.path().join("data/history.tsv");
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("work"))
+            .env_remove("CDPATH")
+            .env("NCD_STORE", &store)
+            .env("NCD_SOURCES", "recent")
+            .arg("proj_x")
+            .assert()
+            .success();
+        assert!(fs::read_to_string(&store).unwrap().contains("proj_x"));
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("other"))
+            .env_remove("CDPATH")
+            .env("NCD_STORE", &store)
+            .env("NCD_SOURCES", "recent")
+            .arg("proj_x")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("proj_x"));
+    }
+}
+
+mod import_export {
+    use std::path::{Path, PathBuf};
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    fn import(store: &Path, from: &str, file: &str) -> assert_cmd::assert::Assert {
+        cargo_bin_cmd!("ncd")
+            .env("NCD_STORE", store)
+            .args(["import", &format!("--from={}", from)])
+            .arg(fixture(file))
+            .assert()
+    }
+
+    fn export(store: &Path, format: &str) -> String {
+        let out = cargo_bin_cmd!("ncd")
+            .env("NCD_STORE", store)
+            .args(["export", &format!("--format={}", format)])
+            .output()
+            .unwrap();
+        assert!(out.status.success());
+        String::from_utf8(out.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_import_each_format_merges_into_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = tmp.path().join("history.tsv");
+
+        import(&store, "z", "z.txt").success().stdout(predicate::str::contains("Imported 3 directories (3 new)"));
+        import(&store, "fasd", "fasd.txt").success().stdout(predicate::str::contains("(1 new)"));
+        import(&store, "autojump", "autojump.txt").success().stdout(predicate::str::contains("(1 new)"));
+        import(&store, "zoxide", "zoxide.db").success().stdout(predicate::str::contains("(1 new)"));
+
+        let csv = export(&store, "csv");
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "path,rank,last");
+        // 42 + 12.25 + 10 + 8, with the latest visit of the four.
+        assert_eq!(lines[1], "/home/dev/src/ncd,72.25,1700000200");
+        assert!(csv.contains("/home/dev/odd|name,1,1699000000"));
+        assert_eq!(lines.len(), 1 + 6);
+    }
+
+    #[test]
+    fn test_csv_export_imports_back() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (a, b) = (tmp.path().join("a.tsv"), tmp.path().join("b.tsv"));
+        import(&a, "csv", "sync.csv").success();
+        let csv = export(&a, "csv");
+        std::fs::write(tmp.path().join("out.csv"), &csv).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .env("NCD_STORE", &b)
+            .args(["import", "--from=csv"])
+            .arg(tmp.path().join("out.csv"))
+            .assert()
+            .success();
+        assert_eq!(export(&b, "csv"), csv);
+        assert!(export(&b, "json").contains(r#"{"path":"/home/dev/a, \"quoted\" dir","rank":2,"last":1700000500}"#));
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = tmp.path().join("history.tsv");
+        import(&store, "csv", "z.txt").failure().stderr(predicate::str::contains("CSV line 1"));
+        cargo_bin_cmd!("ncd")
+            .env("NCD_STORE", &store)
+            .args(["import", "--from=bogus", "x"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid format: bogus"));
+        assert!(!store.exists());
+    }
+}
+
+mod backtracking {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+    use predicates::boolean::PredicateBooleanExt;
+
+    #[test]
+    fn test_later_root_is_tried_when_first_dead_ends() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (r1, r2) = (tmp.path().join("r1"), tmp.path().join("r2"));
+        fs::create_dir_all(r1.join("proj/docs")).unwrap();
+        fs::create_dir_all(r2.join("proj/src")).unwrap();
+        fs::create_dir_all(tmp.path().join("cwd")).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", std::env::join_paths([&r1, &r2]).unwrap())
+            .arg("proj/src")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("r2"));
+    }
+
+    #[test]
+    fn test_first_root_with_a_full_chain_wins() {
+        let tmp = tempfile::tempdir().unwrap();
+        let r1 = tmp.path().join("r1");
+        fs::create_dir_all(r1.join("proj/src")).unwrap();
+        fs::create_dir_all(tmp.path().join("cwd/proj/src")).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", &r1)
+            .arg("proj/src")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("cwd"));
+    }
+
+    #[test]
+    fn test_ambiguity_lists_full_chains() {
+        let tmp = tempfile::tempdir().unwrap();
+        for p in ["proj_a/src/api", "proj_b/src/api", "proj_c/src"] {
+            fs::create_dir_all(tmp.path().join(p)).unwrap();
+        }
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .arg("proj*/src/api")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Ambiguous match")
+                .and(predicate::str::contains("proj_a/src/api"))
+                .and(predicate::str::contains("proj_b/src/api"))
+                .and(predicate::str::contains("proj_c").not()));
+    }
+}
+
+mod query_grammar {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_home_starts_a_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("src/ncd")).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("HOME", tmp.path())
+            .env_remove("USERPROFILE")
+            .env_remove("CDPATH")
+            .arg("~/src/ncd")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("ncd\n"));
+    }
+
+    #[test]
+    fn test_list_home_and_previous() {
+        let tmp = tempfile::tempdir().unwrap();
+        let old = tmp.path().join("old");
+        fs::create_dir_all(&old).unwrap();
+        for args in [&["-l", "~"][..], &["-l"]] {
+            cargo_bin_cmd!("ncd")
+                .current_dir(tmp.path())
+                .env("HOME", tmp.path())
+                .env_remove("USERPROFILE")
+                .env_remove("CDPATH")
+                .args(args)
+                .assert()
+                .success()
+                .stdout(format!("{}\n", tmp.path().display()));
+        }
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("OLDPWD", &old)
+            .env_remove("CDPATH")
+            .args(["-l", "-"])
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("old\n"));
+    }
+
+    #[test]
+    fn test_spaced_dots_report_the_column() {
+        cargo_bin_cmd!("ncd")
+            .env_remove("CDPATH")
+            .arg("x/. . .")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid query at column 4"));
+    }
+
+    #[test]
+    fn test_regex_segment() {
+        let tmp = tempfile::tempdir().unwrap();
+        for p in ["lib/v1.2/src", "lib/v1-beta/src"] {
+            fs::create_dir_all(tmp.path().join(p)).unwrap();
+        }
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .arg(r"lib/re:v\d+\.\d+/src")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("v1.2"));
+    }
+
+    #[test]
+    fn test_bookmark_from_config() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("work/api")).unwrap();
+        let conf = tmp.path().join("ncd.conf");
+        fs::write(&conf, format!("bookmark.w = {}\n", tmp.path().join("work").display())).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("NCD_CONFIG", &conf)
+            .env_remove("CDPATH")
+            .arg(":w/api")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("api"));
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("NCD_CONFIG", &conf)
+            .arg(":nope")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Unknown bookmark :nope ("));
+    }
+}
+
+mod file_paths {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_compiler_location_jumps_to_directory() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("crate/src")).unwrap();
+        fs::write(tmp.path().join("crate/src/lib.rs"), "").unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .arg("crate/src/lib.rs:12:5")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("src\n"));
+    }
+
+    #[test]
+    fn test_bare_file_name_found_in_cdpath_root() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cdp = tmp.path().join("cdp");
+        fs::create_dir_all(tmp.path().join("cwd")).unwrap();
+        fs::create_dir_all(&cdp).unwrap();
+        fs::write(cdp.join("notes.txt"), "").unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", &cdp)
+            .arg("notes.txt:3")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("cdp\n"));
+    }
+}
+
+mod find_by_file {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_file_flag_returns_the_directory() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("ops/deploy/stack")).unwrap();
+        fs::create_dir_all(tmp.path().join("web")).unwrap();
+        fs::write(tmp.path().join("ops/deploy/stack/docker-compose.yml"), "").unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["-f", "docker-compose.yml"])
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("stack\n"));
+    }
+
+    #[test]
+    fn test_has_picks_between_roots() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (r1, r2) = (tmp.path().join("r1"), tmp.path().join("r2"));
+        fs::create_dir_all(r1.join("api")).unwrap();
+        fs::create_dir_all(r2.join("api")).unwrap();
+        fs::write(r2.join("api/Cargo.toml"), "").unwrap();
+        fs::create_dir_all(tmp.path().join("cwd")).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", std::env::join_paths([&r1, &r2]).unwrap())
+            .args(["--has", "Cargo.toml", "api"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("r2"));
+    }
+
+    #[test]
+    fn test_file_flag_reports_ambiguity() {
+        let tmp = tempfile::tempdir().unwrap();
+        for d in ["a", "b"] {
+            fs::create_dir_all(tmp.path().join(d)).unwrap();
+            fs::write(tmp.path().join(d).join("Makefile"), "").unwrap();
+        }
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["--file", "Makefile"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Ambiguous match in Makefile"));
+    }
+
+    #[test]
+    fn test_file_flag_counts_the_root_itself() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("a/b")).unwrap();
+        fs::write(tmp.path().join("Cargo.toml"), "").unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["-l", "-f", "Cargo.toml"])
+            .assert()
+            .success()
+            .stdout(format!("{}\n", tmp.path().display()));
+        fs::write(tmp.path().join("a/b/Cargo.toml"), "").unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["-f", "Cargo.toml"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Ambiguous match in Cargo.toml"))
+            .stderr(predicate::str::contains(format!("-> {}\n", tmp.path().display())));
+    }
+}
+
+mod crate_sources {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    const LOCK: &str = "[[package]]\nname = \"serde\"\nversion = \"1.0.2\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n[[package]]\nname = \"tokio\"\nversion = \"1.3.0\"\nsource = \"git+https://github.com/tokio-rs/tokio?branch=master#0123456789abcdef\"\n";
+
+    fn cargo_home(root: &std::path::Path) -> std::path::PathBuf {
+        let home = root.join("cargo-home");
+        for d in ["serde-1.0.1/src", "serde-1.0.2/src", "serde_json-1.0.0"] {
+            fs::create_dir_all(home.join("registry/src/index.crates.io-6f17d22bba15001f").join(d)).unwrap();
+        }
+        let checkout = home.join("git/checkouts/tokio-1a2b3c4d/0123456");
+        fs::create_dir_all(checkout.join("tokio/src")).unwrap();
+        fs::write(checkout.join("Cargo.toml"), "[workspace]\nmembers = [\"tokio\"]\n").unwrap();
+        fs::write(checkout.join("tokio/Cargo.toml"), "[package]\nname = \"tokio\"\n").unwrap();
+        home
+    }
+
+    #[test]
+    fn test_lock_file_picks_the_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let home = cargo_home(tmp.path());
+        fs::create_dir_all(tmp.path().join("app/src")).unwrap();
+        fs::write(tmp.path().join("app/Cargo.lock"), LOCK).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("app/src"))
+            .env("CARGO_HOME", &home)
+            .arg("crate:serde/src")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("serde-1.0.2/src\n"));
+    }
+
+    #[test]
+    fn test_git_checkout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let home = cargo_home(tmp.path());
+        fs::write(tmp.path().join("Cargo.lock"), LOCK).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("CARGO_HOME", &home)
+            .arg("crate:tokio")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("0123456/tokio\n"));
+    }
+
+    #[test]
+    fn test_versions_without_lock_are_ambiguous() {
+        let tmp = tempfile::tempdir().unwrap();
+        let home = cargo_home(tmp.path());
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("CARGO_HOME", &home)
+            .arg("crate:serde")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Ambiguous match"))
+            .stderr(predicate::str::contains("serde_json").not());
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("CARGO_HOME", &home)
+            .arg("crate:serde@1.0.1")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("serde-1.0.1\n"));
+    }
+
+    #[test]
+    fn test_missing_name_is_a_parse_error() {
+        cargo_bin_cmd!("ncd")
+            .arg("crate:@1")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("column 7: expected a crate name"));
+    }
+}
+
+mod workspace_members {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_pnpm_member_with_tail() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("pnpm-workspace.yaml"), "packages:\n  - 'apps/*'\n").unwrap();
+        fs::create_dir_all(tmp.path().join("apps/frontend/src")).unwrap();
+        fs::write(tmp.path().join("apps/frontend/package.json"), "{\"name\": \"@shop/web\"}").unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("apps"))
+            .env_remove("CDPATH")
+            .arg("ws:web/src")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("frontend/src\n"));
+    }
+
+    #[test]
+    fn test_same_name_is_ambiguous() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join("package.json"), "{\"workspaces\": [\"a/*\", \"b/*\"]}").unwrap();
+        for d in ["a/ui", "b/ui"] {
+            fs::create_dir_all(tmp.path().join(d)).unwrap();
+            fs::write(tmp.path().join(d).join("package.json"), format!("{{\"name\": \"@{}/ui\"}}", &d[..1])).unwrap();
+        }
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .arg("ws:ui")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Ambiguous match"));
+    }
+}
+
+mod git_remotes {
+    use std::fs;
+    use std::path::Path;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    fn clone(dir: &Path, url: &str) {
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join(".git/config"), format!("[remote \"origin\"]\n\turl = {}\n", url)).unwrap();
+    }
+
+    fn setup() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        clone(&tmp.path().join("code/work/backend"), "git@github.com:acme/api.git");
+        clone(&tmp.path().join("code/forks/api"), "https://github.com/someone/api");
+        clone(&tmp.path().join("code/misc/tools"), "https://github.com/acme/dev-tools");
+        fs::create_dir_all(tmp.path().join("cwd")).unwrap();
+        tmp
+    }
+
+    #[test]
+    fn test_resolve_by_org_and_repo_with_tail() {
+        let tmp = setup();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", tmp.path().join("code"))
+            .arg("git:acme/api/src")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("backend/src\n"));
+    }
+
+    #[test]
+    fn test_partial_name_and_ambiguity() {
+        let tmp = setup();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", tmp.path().join("code"))
+            .arg("git:tools")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("misc/tools\n"));
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", tmp.path().join("code"))
+            .arg("git:api")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Ambiguous match"));
+    }
+
+    #[test]
+    fn test_list_mode() {
+        let tmp = setup();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", tmp.path().join("code"))
+            .args(["--list", "git:api"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("backend").and(predicate::str::contains("forks")));
+    }
+}
+
+mod worktrees_and_submodules {
+    use std::fs;
+    use std::path::Path;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    fn write(path: &Path, text: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn test_branch_with_slash_and_tail() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (main, linked) = (tmp.path().join("repo"), tmp.path().join("elsewhere/hotfix"));
+        write(&main.join(".git/HEAD"), "ref: refs/heads/main\n");
+        write(&main.join(".git/worktrees/hotfix/HEAD"), "ref: refs/heads/fix/login\n");
+        write(&main.join(".git/worktrees/hotfix/gitdir"), &format!("{}\n", linked.join(".git").display()));
+        fs::create_dir_all(linked.join("src")).unwrap();
+        fs::create_dir_all(main.join("docs")).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(main.join("docs"))
+            .env_remove("CDPATH")
+            .arg("wt:fix/login/src")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("hotfix/src\n"));
+    }
+
+    #[test]
+    fn test_submodule_by_name() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = tmp.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(repo.join("third_party/zlib")).unwrap();
+        write(&repo.join(".gitmodules"), "[submodule \"compression\"]\n\tpath = third_party/zlib\n");
+        cargo_bin_cmd!("ncd")
+            .current_dir(&repo)
+            .env_remove("CDPATH")
+            .arg("sub:compression")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("third_party/zlib\n"));
+        cargo_bin_cmd!("ncd")
+            .current_dir(&repo)
+            .env_remove("CDPATH")
+            .arg("sub:")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("expected a submodule after 'sub:'"));
+    }
+}
+
+mod known_folders {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_user_dirs_with_tail() {
+        let tmp = tempfile::tempdir().unwrap();
+        let home = tmp.path().join("home");
+        fs::create_dir_all(home.join(".config")).unwrap();
+        fs::create_dir_all(home.join("Telechargements/isos")).unwrap();
+        fs::write(home.join(".config/user-dirs.dirs"), "XDG_DOWNLOAD_DIR=\"$HOME/Telechargements\"\n").unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("HOME", &home)
+            .env_remove("USERPROFILE")
+            .env_remove("XDG_CONFIG_HOME")
+            .env("NCD_CONFIG", tmp.path().join("none.conf"))
+            .arg(":downloads/isos")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("Telechargements/isos\n"));
+    }
+
+    #[test]
+    fn test_env_folders_and_bookmark_precedence() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cargo = tmp.path().join("cargo-home");
+        fs::create_dir_all(cargo.join("registry")).unwrap();
+        fs::create_dir_all(tmp.path().join("mine")).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("CARGO_HOME", &cargo)
+            .env("NCD_CONFIG", tmp.path().join("none.conf"))
+            .arg(":cargo/registry")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("cargo-home/registry\n"));
+        let config = tmp.path().join("ncd.conf");
+        fs::write(&config, format!("bookmark.cargo = {}\n", tmp.path().join("mine").display())).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env("CARGO_HOME", &cargo)
+            .env("NCD_CONFIG", &config)
+            .arg(":cargo")
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("mine\n"));
+    }
+}
+
+mod mkdir {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_creates_the_missing_tail_below_cdpath_match() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("root");
+        fs::create_dir_all(root.join("project/notes")).unwrap();
+        fs::create_dir_all(tmp.path().join("cwd")).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path().join("cwd"))
+            .env("CDPATH", &root)
+            .args(["--mkdir", "project/notes/2026/q1"])
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("project/notes/2026/q1\n"));
+        assert!(root.join("project/notes/2026/q1").is_dir());
+    }
+
+    #[test]
+    fn test_relative_to_cwd_and_existing_paths_untouched() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("a")).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["-p", "new/dir"])
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("new/dir\n"));
+        assert!(tmp.path().join("new/dir").is_dir());
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["-p", "a"])
+            .assert()
+            .success()
+            .stdout(predicate::str::ends_with("a\n"));
+    }
+
+    #[test]
+    fn test_refuses_globs() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("x")).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["-p", "x/new*"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("only creates plain names"));
+        assert!(!tmp.path().join("x/new*").exists());
+    }
+
+    #[test]
+    #[cfg(not(any(windows, target_os = "macos")))] // needs a case-sensitive file system
+    fn test_refuses_ambiguous_prefix() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir_all(tmp.path().join("Lib")).unwrap();
+        fs::create_dir_all(tmp.path().join("LIB")).unwrap();
+        cargo_bin_cmd!("ncd")
+            .current_dir(tmp.path())
+            .env_remove("CDPATH")
+            .args(["-p", "lib/new"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Ambiguous match"));
+        assert!(!tmp.path().join("Lib/new").exists() && !tmp.path().join("LIB/new").exists());
+    }
+}
+
+mod scratch_dirs {
+    use std::fs;
+    use assert_cmd::cargo_bin_cmd;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_tmp_creates_and_clean_removes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path().join("scratch");
+        let out = cargo_bin_cmd!("ncd")
+            .env("NCD_TMP", &base)
+            .args(["--tmp", "repro"])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let dir = std::path::PathBuf::from(String::from_utf8(out).unwrap().trim_end());
+        assert!(dir.is_dir() && dir.starts_with(&base));
+        assert!(dir.file_name().unwrap().to_str().unwrap().starts_with("repro-"));
+        fs::create_dir_all(base.join("notes")).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .env("NCD_TMP", &base)
+            .args(["--tmp", "--clean"])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Removed 0"));
+        cargo_bin_cmd!("ncd")
+            .env("NCD_TMP", &base)
+            .args(["--tmp", "--clean", "--older-than", "0s"])
+            .assert()
+            .success()
+            .stdout(predicate::str::is_empty())
+            .stderr(predicate::str::contains("Removed 1"));
+        assert!(!dir.exists() && base.join("notes").is_dir());
+    }
+
+    // Opening a directory to set its mtime is Unix-only in std.
+    #[cfg(unix)]
+    #[test]
+    fn test_clean_keeps_dir_with_recent_nested_edit() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path().join("scratch");
+        let dir = base.join("work-abc");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join(".ncd-tmp"), b"").unwrap();
+        fs::write(dir.join("src").join("main.rs"), b"old").unwrap();
+        let old = std::time::SystemTime::now() - std::time::Duration::from_secs(10 * 86_400);
+        for path in [dir.join(".ncd-tmp"), dir.join("src"), dir.clone()] {
+            fs::File::open(&path).unwrap().set_modified(old).unwrap();
+        }
+        // An edit leaves the directories' own mtimes as they were.
+        fs::write(dir.join("src").join("main.rs"), b"new").unwrap();
+        fs::File::open(dir.join("src")).unwrap().set_modified(old).unwrap();
+
+        cargo_bin_cmd!("ncd")
+            .env("NCD_TMP", &base)
+            .args(["--tmp", "--clean", "--older-than", "7d"])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Removed 0"));
+        assert!(dir.join("src").join("main.rs").is_file());
+    }
+
+    #[test]
+    fn test_flags_need_tmp() {
+        cargo_bin_cmd!("ncd")
+            .arg("--clean")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("need --tmp"));
+        cargo_bin_cmd!("ncd")
+            .args(["--tmp", "a/b"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("plain name"));
+    }
+}